@@ -2,6 +2,16 @@
 # Example script to demonstrate product comparison
 
 BASE_URL="http://localhost:3000/api/v1"
+# Create one with: utilbench-backend keys create demo --scope write
+API_KEY="${API_KEY:?Set API_KEY to a key with the write scope}"
+
+# Adds a retailer offer: product id, retailer, price in CZK cents, stock status
+add_offer() {
+  curl -s -X POST "$BASE_URL/products/$1/offers" \
+    -H "Content-Type: application/json" \
+    -H "Authorization: Bearer $API_KEY" \
+    -d "{\"retailer\": \"$2\", \"price\": $3, \"currency\": \"CZK\", \"stock_status\": \"$4\"}" > /dev/null
+}
 
 echo "=== Product Comparison Demo ==="
 echo ""
@@ -16,18 +26,19 @@ echo ""
 # Add Intel Core i9-13900K
 CPU1=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 1,
     "name": "Intel Core i9-13900K",
     "manufacturer": "Intel",
     "model": "i9-13900K",
-    "price": 589.99,
     "specifications": {
       "cores": 24,
       "threads": 32,
       "base_clock": "3.0",
       "boost_clock": "5.8",
       "tdp": "125",
+      "benchmark_score": 38500,
       "socket": "LGA1700",
       "cache": "36",
       "l1_cache": "1920",
@@ -55,18 +66,19 @@ echo "Added Intel i9-13900K (ID: $CPU1)"
 # Add AMD Ryzen 9 7950X
 CPU2=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 1,
     "name": "AMD Ryzen 9 7950X",
     "manufacturer": "AMD",
     "model": "7950X",
-    "price": 549.99,
     "specifications": {
       "cores": 16,
       "threads": 32,
       "base_clock": "4.5",
       "boost_clock": "5.7",
       "tdp": "170",
+      "benchmark_score": 38000,
       "socket": "AM5",
       "cache": "64",
       "l1_cache": "1024",
@@ -94,18 +106,19 @@ echo "Added AMD Ryzen 9 7950X (ID: $CPU2)"
 # Add Intel Core i5-13600K
 CPU3=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 1,
     "name": "Intel Core i5-13600K",
     "manufacturer": "Intel",
     "model": "i5-13600K",
-    "price": 319.99,
     "specifications": {
       "cores": 14,
       "threads": 20,
       "base_clock": "3.5",
       "boost_clock": "5.1",
       "tdp": "125",
+      "benchmark_score": 24000,
       "socket": "LGA1700",
       "cache": "24",
       "l1_cache": "1120",
@@ -133,18 +146,19 @@ echo "Added Intel i5-13600K (ID: $CPU3)"
 # Add AMD Ryzen 7 7800X3D
 CPU4=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 1,
     "name": "AMD Ryzen 7 7800X3D",
     "manufacturer": "AMD",
     "model": "7800X3D",
-    "price": 449.99,
     "specifications": {
       "cores": 8,
       "threads": 16,
       "base_clock": "4.2",
       "boost_clock": "5.0",
       "tdp": "120",
+      "benchmark_score": 18500,
       "socket": "AM5",
       "cache": "96",
       "l1_cache": "512",
@@ -172,18 +186,19 @@ echo "Added AMD Ryzen 7 7800X3D (ID: $CPU4)"
 # Add Intel Core i7-14700K
 CPU5=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 1,
     "name": "Intel Core i7-14700K",
     "manufacturer": "Intel",
     "model": "i7-14700K",
-    "price": 419.99,
     "specifications": {
       "cores": 20,
       "threads": 28,
       "base_clock": "3.4",
       "boost_clock": "5.6",
       "tdp": "125",
+      "benchmark_score": 35000,
       "socket": "LGA1700",
       "cache": "33",
       "l1_cache": "1600",
@@ -208,6 +223,17 @@ CPU5=$(curl -s -X POST "$BASE_URL/products" \
   }' | jq -r '.product.id')
 echo "Added Intel i7-14700K (ID: $CPU5)"
 
+echo ""
+echo "Adding retailer offers..."
+add_offer $CPU1 "Alza.cz" 1449000 in_stock
+add_offer $CPU1 "CZC.cz" 1419000 out_of_stock
+add_offer $CPU2 "Alza.cz" 1329000 in_stock
+add_offer $CPU2 "Mall.cz" 1359000 in_stock
+add_offer $CPU3 "CZC.cz" 789000 in_stock
+add_offer $CPU4 "Alza.cz" 1099000 in_stock
+add_offer $CPU4 "CZC.cz" 1069000 preorder
+add_offer $CPU5 "Mall.cz" 1029000 in_stock
+
 echo ""
 echo "3. Comparing all five CPUs:"
 echo ""
@@ -227,12 +253,12 @@ echo ""
 # Add Panasonic NN-SN96JS
 MW1=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 4,
     "name": "Panasonic NN-SN96JS",
     "manufacturer": "Panasonic",
     "model": "NN-SN96JS",
-    "price": 329.95,
     "specifications": {
       "wattage": "1250",
       "capacity": "2.2",
@@ -265,12 +291,12 @@ echo "Added Panasonic NN-SN96JS (ID: $MW1)"
 # Add Toshiba EM131A5C-BS
 MW2=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 4,
     "name": "Toshiba EM131A5C-BS",
     "manufacturer": "Toshiba",
     "model": "EM131A5C-BS",
-    "price": 159.99,
     "specifications": {
       "wattage": "1100",
       "capacity": "1.2",
@@ -303,12 +329,12 @@ echo "Added Toshiba EM131A5C-BS (ID: $MW2)"
 # Add Breville BMO870BSS
 MW3=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 4,
     "name": "Breville Combi Wave 3-in-1",
     "manufacturer": "Breville",
     "model": "BMO870BSS",
-    "price": 499.95,
     "specifications": {
       "wattage": "1200",
       "capacity": "1.1",
@@ -341,12 +367,12 @@ echo "Added Breville Combi Wave 3-in-1 (ID: $MW3)"
 # Add Samsung MS14K6000AS
 MW4=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 4,
     "name": "Samsung MS14K6000AS",
     "manufacturer": "Samsung",
     "model": "MS14K6000AS",
-    "price": 229.99,
     "specifications": {
       "wattage": "1000",
       "capacity": "1.4",
@@ -379,12 +405,12 @@ echo "Added Samsung MS14K6000AS (ID: $MW4)"
 # Add GE JES1097SMSS
 MW5=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 4,
     "name": "GE JES1097SMSS",
     "manufacturer": "GE",
     "model": "JES1097SMSS",
-    "price": 189.99,
     "specifications": {
       "wattage": "900",
       "capacity": "0.9",
@@ -417,12 +443,12 @@ echo "Added GE JES1097SMSS (ID: $MW5)"
 # Add LG LMC2075ST
 MW6=$(curl -s -X POST "$BASE_URL/products" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "category_id": 4,
     "name": "LG LMC2075ST NeoChef",
     "manufacturer": "LG",
     "model": "LMC2075ST",
-    "price": 379.99,
     "specifications": {
       "wattage": "1200",
       "capacity": "2.0",
@@ -451,15 +477,25 @@ MW6=$(curl -s -X POST "$BASE_URL/products" \
     }
   }' | jq -r '.product.id')
 echo "Added LG LMC2075ST NeoChef (ID: $MW6)"
+
+echo ""
+echo "Adding retailer offers..."
+add_offer $MW1 "Datart.cz" 799000 in_stock
+add_offer $MW2 "Datart.cz" 389000 in_stock
+add_offer $MW2 "Alza.cz" 369000 in_stock
+add_offer $MW3 "Alza.cz" 1199000 in_stock
+add_offer $MW4 "Mall.cz" 559000 out_of_stock
+add_offer $MW5 "Datart.cz" 459000 in_stock
+add_offer $MW6 "Alza.cz" 899000 in_stock
 echo ""
 echo "6. Comparing all six microwaves:"
 echo ""
 curl -s "$BASE_URL/products/compare?ids=$MW1,$MW2,$MW3,$MW4,$MW5,$MW6" | jq '.'
 
 echo ""
-echo "7. Search demo - Find microwaves under $200:"
+echo "7. Search demo - Find microwaves under 5000 CZK:"
 echo ""
-curl -s "$BASE_URL/products/search?category=microwaves&max_price=200" | jq '.products[] | {name, manufacturer, price}'
+curl -s "$BASE_URL/products/search?category=microwaves&max_price=500000" | jq '.products[] | {name, manufacturer, price, currency, offer_count}'
 
 echo ""
 echo "=== Demo Complete ==="
@@ -0,0 +1,4 @@
+-- Categories can now be managed through the API as well as schemas.yml.
+-- Rows edited through the API are no longer overwritten by the startup sync.
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS source VARCHAR(20) NOT NULL DEFAULT 'yaml';
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS archived_at TIMESTAMP WITH TIME ZONE;
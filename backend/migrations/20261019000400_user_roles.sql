@@ -0,0 +1,3 @@
+-- Roles gating catalog actions: contributor < editor < moderator < admin
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'contributor'
+    CHECK (role IN ('contributor', 'editor', 'moderator', 'admin'));
@@ -0,0 +1,31 @@
+-- Full snapshot of every version of a product
+CREATE TABLE IF NOT EXISTS product_revisions (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    actor_type VARCHAR(20) NOT NULL,
+    actor_id INTEGER,
+    actor_name VARCHAR(255) NOT NULL,
+    snapshot JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, revision)
+);
+
+-- Existing products start their history with their current state
+INSERT INTO product_revisions (product_id, revision, action, actor_type, actor_name, snapshot, created_at)
+SELECT p.id, 1, 'create', 'system', 'system',
+    jsonb_build_object(
+        'id', p.id,
+        'category_id', p.category_id,
+        'category_name', c.name,
+        'name', p.name,
+        'manufacturer', p.manufacturer,
+        'model', p.model,
+        'specifications', p.specifications,
+        'price', p.price::text
+    ),
+    COALESCE(p.updated_at, NOW())
+FROM products p
+JOIN categories c ON p.category_id = c.id
+WHERE NOT EXISTS (SELECT 1 FROM product_revisions r WHERE r.product_id = p.id);
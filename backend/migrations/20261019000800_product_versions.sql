@@ -0,0 +1,2 @@
+-- Version counter for optimistic concurrency control, exposed as the product ETag
+ALTER TABLE products ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
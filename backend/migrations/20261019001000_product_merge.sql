@@ -0,0 +1,8 @@
+-- Duplicates merged into another product keep their row, so old ids can
+-- redirect to the surviving product and their history stays available.
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_status_check;
+ALTER TABLE products ADD CONSTRAINT products_status_check
+    CHECK (status IN ('pending', 'approved', 'rejected', 'changes_requested', 'merged'));
+ALTER TABLE products ADD COLUMN IF NOT EXISTS merged_into INTEGER REFERENCES products(id);
+
+CREATE INDEX IF NOT EXISTS idx_products_merged_into ON products(merged_into);
@@ -0,0 +1,60 @@
+-- Every slug a product has had. The current one is also stored on the product,
+-- earlier ones stay here so old URLs keep resolving after a rename.
+CREATE TABLE IF NOT EXISTS product_slugs (
+    slug VARCHAR(255) PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_product_slugs_product_id ON product_slugs(product_id);
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS slug VARCHAR(255);
+
+-- Existing products get a slug from their name. Names whose plain slug is
+-- free claim it first, the rest are numbered with the next free suffix, so
+-- "Foo", "Foo" and "Foo 2" become foo, foo-3 and foo-2.
+DO $$
+DECLARE
+    product RECORD;
+    candidate VARCHAR(255);
+    n INTEGER;
+BEGIN
+    FOR product IN
+        SELECT id, COALESCE(
+            NULLIF(TRIM(BOTH '-' FROM LOWER(REGEXP_REPLACE(name, '[^a-zA-Z0-9]+', '-', 'g'))), ''),
+            'product'
+        ) AS base
+        FROM products
+        WHERE slug IS NULL
+        ORDER BY id
+    LOOP
+        IF NOT EXISTS (SELECT 1 FROM products WHERE slug = product.base) THEN
+            UPDATE products SET slug = product.base WHERE id = product.id;
+        END IF;
+    END LOOP;
+
+    FOR product IN
+        SELECT id, COALESCE(
+            NULLIF(TRIM(BOTH '-' FROM LOWER(REGEXP_REPLACE(name, '[^a-zA-Z0-9]+', '-', 'g'))), ''),
+            'product'
+        ) AS base
+        FROM products
+        WHERE slug IS NULL
+        ORDER BY id
+    LOOP
+        n := 2;
+        LOOP
+            candidate := product.base || '-' || n;
+            EXIT WHEN NOT EXISTS (SELECT 1 FROM products WHERE slug = candidate);
+            n := n + 1;
+        END LOOP;
+        UPDATE products SET slug = candidate WHERE id = product.id;
+    END LOOP;
+END $$;
+
+INSERT INTO product_slugs (slug, product_id)
+SELECT slug, id FROM products
+ON CONFLICT (slug) DO NOTHING;
+
+ALTER TABLE products ALTER COLUMN slug SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_slug ON products(slug);
@@ -0,0 +1,11 @@
+-- Comparisons saved under a short public id for sharing. A frozen comparison
+-- keeps the table as it was at save time in `snapshot`.
+CREATE TABLE IF NOT EXISTS saved_comparisons (
+    id VARCHAR(16) PRIMARY KEY,
+    product_ids INTEGER[] NOT NULL,
+    options JSONB NOT NULL DEFAULT '{}'::jsonb,
+    snapshot JSONB,
+    created_by_type VARCHAR(20) NOT NULL,
+    created_by_id INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+-- Named scoring weights per category, used by the "best products" endpoint.
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS weight_profiles JSONB NOT NULL DEFAULT '{}';
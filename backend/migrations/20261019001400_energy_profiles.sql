@@ -0,0 +1,2 @@
+-- Power field and default usage per category, for energy running-cost estimates.
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS energy_profile JSONB;
@@ -0,0 +1,17 @@
+-- Every price a product has had, recorded whenever a write changes it.
+CREATE TABLE IF NOT EXISTS price_history (
+    id BIGSERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    price DECIMAL(10, 2) NOT NULL,
+    source VARCHAR(20) NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_product ON price_history (product_id, recorded_at);
+
+-- Existing products start their history with their current price
+INSERT INTO price_history (product_id, price, source, recorded_at)
+SELECT p.id, p.price, 'initial', COALESCE(p.updated_at, NOW())
+FROM products p
+WHERE p.price IS NOT NULL
+    AND NOT EXISTS (SELECT 1 FROM price_history h WHERE h.product_id = p.id);
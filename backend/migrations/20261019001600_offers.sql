@@ -0,0 +1,35 @@
+-- Retailer offers for a product, several per product. The product's price,
+-- currency and offer count follow its offers: the price is the lowest
+-- in-stock one, see `offers::refresh_price`.
+CREATE TABLE IF NOT EXISTS offers (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    retailer VARCHAR(100) NOT NULL,
+    price DECIMAL(10, 2) NOT NULL CHECK (price >= 0),
+    currency VARCHAR(3) NOT NULL,
+    url TEXT,
+    stock_status VARCHAR(20) NOT NULL DEFAULT 'unknown'
+        CHECK (stock_status IN ('in_stock', 'out_of_stock', 'preorder', 'unknown')),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (product_id, retailer)
+);
+
+CREATE INDEX IF NOT EXISTS idx_offers_product ON offers (product_id);
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS currency VARCHAR(3);
+ALTER TABLE products ADD COLUMN IF NOT EXISTS offer_count INTEGER NOT NULL DEFAULT 0;
+
+-- Existing prices were entered in dollars without a shop; they become an
+-- in-stock offer from an unknown retailer until editors replace them
+INSERT INTO offers (product_id, retailer, price, currency, stock_status, last_seen_at)
+SELECT p.id, 'Unknown retailer', p.price, 'USD', 'in_stock', COALESCE(p.updated_at, NOW())
+FROM products p
+WHERE p.price IS NOT NULL
+ON CONFLICT (product_id, retailer) DO NOTHING;
+
+UPDATE products p
+SET currency = 'USD',
+    offer_count = (SELECT COUNT(*) FROM offers o WHERE o.product_id = p.id)
+WHERE p.price IS NOT NULL;
@@ -0,0 +1,25 @@
+-- The slug of the name a slug was claimed for. A numbered slug like
+-- "rtx-2" has base "rtx", while a name that ends in a number like "RTX 4090"
+-- gives "rtx-4090" with base "rtx-4090".
+ALTER TABLE product_slugs ADD COLUMN IF NOT EXISTS base VARCHAR(255);
+
+-- Current slugs were claimed for the product's name, earlier ones are taken
+-- as the plain slug of the name they had
+WITH names AS (
+    SELECT id, slug, COALESCE(
+        NULLIF(TRIM(BOTH '-' FROM LOWER(REGEXP_REPLACE(name, '[^a-zA-Z0-9]+', '-', 'g'))), ''),
+        'product'
+    ) AS base
+    FROM products
+)
+UPDATE product_slugs s
+SET base = CASE
+    WHEN n.slug = s.slug AND (s.slug = n.base OR s.slug ~ ('^' || n.base || '-[0-9]+$')) THEN n.base
+    ELSE s.slug
+END
+FROM names n
+WHERE n.id = s.product_id AND s.base IS NULL;
+
+UPDATE product_slugs SET base = slug WHERE base IS NULL;
+
+ALTER TABLE product_slugs ALTER COLUMN base SET NOT NULL;
@@ -0,0 +1,7 @@
+-- The CPU spec field "score" became "benchmark_score", so it no longer shares
+-- its name with the composite score used by Pareto charts and best products
+UPDATE products
+SET specifications = (specifications - 'score')
+    || jsonb_build_object('benchmark_score', specifications -> 'score')
+WHERE specifications ? 'score'
+    AND category_id IN (SELECT id FROM categories WHERE slug = 'cpus');
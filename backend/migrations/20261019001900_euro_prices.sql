@@ -0,0 +1,20 @@
+-- Prices normalized to euros at the configured exchange rates, so products
+-- priced in different currencies can be filtered, ranked and compared. The
+-- application fills them in on startup, see `offers::sync_prices`.
+ALTER TABLE products ADD COLUMN IF NOT EXISTS price_eur DECIMAL(12, 2);
+
+CREATE INDEX IF NOT EXISTS idx_products_price_eur ON products (price_eur);
+
+-- History entries keep the currency they were recorded in. Entries from
+-- before offers were in dollars; later ones take the product's currency.
+ALTER TABLE price_history ADD COLUMN IF NOT EXISTS currency VARCHAR(3);
+ALTER TABLE price_history ADD COLUMN IF NOT EXISTS price_eur DECIMAL(12, 2);
+
+UPDATE price_history h
+SET currency = CASE
+    WHEN h.source IN ('initial', 'create', 'update', 'restore') THEN 'USD'
+    ELSE COALESCE((SELECT p.currency FROM products p WHERE p.id = h.product_id), 'USD')
+END
+WHERE h.currency IS NULL;
+
+ALTER TABLE price_history ALTER COLUMN currency SET NOT NULL;
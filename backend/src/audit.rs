@@ -0,0 +1,155 @@
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{HeaderName, HeaderValue, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use rand::RngCore;
+use serde::Serialize;
+use std::convert::Infallible;
+
+use crate::{auth::Actor, diff::json_differences};
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Identifier of the current request, taken from `X-Request-Id` or generated.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Middleware assigning every request an id and echoing it in the response.
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 64)
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let mut bytes = [0u8; 16];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            bytes.iter().map(|b| format!("{:02x}", b)).collect()
+        });
+
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+
+    response
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(|| RequestId(String::new())))
+    }
+}
+
+/// Who made a change, as recorded in the audit log.
+pub enum AuditActor<'a> {
+    Actor(&'a Actor),
+    /// Changes made by the server itself, like the schemas.yml sync
+    System,
+}
+
+/// A single change to be appended to the audit log.
+pub struct AuditEntry<'a> {
+    pub actor: AuditActor<'a>,
+    pub request_id: Option<&'a str>,
+    pub action: &'a str,
+    pub entity_type: &'a str,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+impl<'a> AuditEntry<'a> {
+    pub fn new(
+        actor: &'a Actor,
+        request_id: &'a RequestId,
+        action: &'a str,
+        entity_type: &'a str,
+        entity_id: impl ToString,
+    ) -> Self {
+        AuditEntry {
+            actor: AuditActor::Actor(actor),
+            request_id: Some(request_id.0.as_str()).filter(|id| !id.is_empty()),
+            action,
+            entity_type,
+            entity_id: entity_id.to_string(),
+            before: None,
+            after: None,
+        }
+    }
+
+    /// An entry for a change made by the server itself rather than a request.
+    pub fn system(action: &'a str, entity_type: &'a str, entity_id: impl ToString) -> Self {
+        AuditEntry {
+            actor: AuditActor::System,
+            request_id: None,
+            action,
+            entity_type,
+            entity_id: entity_id.to_string(),
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn before(mut self, value: &impl Serialize) -> Self {
+        self.before = serde_json::to_value(value).ok();
+        self
+    }
+
+    pub fn after(mut self, value: &impl Serialize) -> Self {
+        self.after = serde_json::to_value(value).ok();
+        self
+    }
+
+    /// Appends the entry. Pass the transaction that made the change, so the
+    /// change and its audit entry are committed together.
+    pub async fn record<'e, E>(self, executor: E) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::PgExecutor<'e>,
+    {
+        let (actor_type, actor_id, actor_name) = match self.actor {
+            AuditActor::Actor(actor) => (actor.kind(), Some(actor.id()), actor.name().to_string()),
+            AuditActor::System => ("system", None, "system".to_string()),
+        };
+
+        let null = serde_json::Value::Null;
+        let changes = json_differences(
+            self.before.as_ref().unwrap_or(&null),
+            self.after.as_ref().unwrap_or(&null),
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO audit_log (actor_type, actor_id, actor_name, request_id, action, entity_type, entity_id, before, after, changes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(actor_type)
+        .bind(actor_id)
+        .bind(actor_name)
+        .bind(self.request_id)
+        .bind(self.action)
+        .bind(self.entity_type)
+        .bind(&self.entity_id)
+        .bind(&self.before)
+        .bind(&self.after)
+        .bind(serde_json::to_value(&changes).unwrap_or_default())
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
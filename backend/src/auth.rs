@@ -0,0 +1,419 @@
+use std::{fmt, str::FromStr};
+
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use axum::{
+    extract::FromRequestParts,
+    http::{StatusCode, header::AUTHORIZATION, request::Parts},
+};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{models::User, routes::AppState};
+
+/// Prefix of every generated API key, so leaked keys are easy to recognise.
+const KEY_PREFIX: &str = "ub_";
+
+/// Prefix of session tokens issued on login.
+const SESSION_PREFIX: &str = "ubs_";
+
+/// How long a login session stays valid.
+pub const SESSION_TTL: Duration = Duration::days(30);
+
+/// Permission level of an API key. Each scope includes the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+            Scope::Admin => "admin",
+        }
+    }
+
+    /// The role an API key acts with. `read` keys cannot modify anything.
+    pub fn role(&self) -> Option<Role> {
+        match self {
+            Scope::Read => None,
+            Scope::Write => Some(Role::Editor),
+            Scope::Admin => Some(Role::Admin),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Scope::Read),
+            "write" => Ok(Scope::Write),
+            "admin" => Ok(Scope::Admin),
+            other => Err(format!(
+                "unknown scope '{}' (expected read, write or admin)",
+                other
+            )),
+        }
+    }
+}
+
+/// Role of a user account. Each role includes the permissions of the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Can submit products
+    Contributor,
+    /// Can change specifications and prices
+    Editor,
+    /// Can approve submissions
+    Moderator,
+    /// Can manage categories, schemas and roles
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Contributor => "contributor",
+            Role::Editor => "editor",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "contributor" => Ok(Role::Contributor),
+            "editor" => Ok(Role::Editor),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("unknown role '{}'", other)),
+        }
+    }
+}
+
+impl TryFrom<String> for Role {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// The API key a request was authenticated with.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    pub scope: Scope,
+}
+
+/// Whoever a request was authenticated as: a logged-in user or an API key.
+#[derive(Debug, Clone)]
+pub enum Actor {
+    User(User),
+    ApiKey(ApiKey),
+}
+
+impl Actor {
+    pub fn role(&self) -> Option<Role> {
+        match self {
+            Actor::User(user) => Some(user.role),
+            Actor::ApiKey(key) => key.scope.role(),
+        }
+    }
+
+    /// `"user"` or `"api_key"`, as stored alongside recorded changes.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Actor::User(_) => "user",
+            Actor::ApiKey(_) => "api_key",
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        match self {
+            Actor::User(user) => user.id,
+            Actor::ApiKey(key) => key.id,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Actor::User(user) => &user.display_name,
+            Actor::ApiKey(key) => &key.name,
+        }
+    }
+
+    pub fn user_id(&self) -> Option<i32> {
+        match self {
+            Actor::User(user) => Some(user.id),
+            Actor::ApiKey(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Actor::User(user) => write!(f, "user #{} '{}'", user.id, user.display_name),
+            Actor::ApiKey(key) => write!(f, "key #{} '{}'", key.id, key.name),
+        }
+    }
+}
+
+/// Declares an extractor that only admits actors holding at least the given role.
+macro_rules! role_extractor {
+    ($(#[$meta:meta])* $name:ident, $role:expr) => {
+        $(#[$meta])*
+        pub struct $name(pub Actor);
+
+        impl FromRequestParts<AppState> for $name {
+            type Rejection = StatusCode;
+
+            async fn from_request_parts(
+                parts: &mut Parts,
+                state: &AppState,
+            ) -> Result<Self, Self::Rejection> {
+                authorize(parts, state, $role).await.map($name)
+            }
+        }
+    };
+}
+
+role_extractor!(
+    /// Extractor for product submissions. Requires the `contributor` role.
+    ContributorAuth,
+    Role::Contributor
+);
+role_extractor!(
+    /// Extractor for editing specifications and prices. Requires the `editor` role.
+    EditorAuth,
+    Role::Editor
+);
+role_extractor!(
+    /// Extractor for moderation actions. Requires the `moderator` role.
+    ModeratorAuth,
+    Role::Moderator
+);
+role_extractor!(
+    /// Extractor for administrative endpoints. Requires the `admin` role.
+    AdminAuth,
+    Role::Admin
+);
+
+async fn authorize(parts: &Parts, state: &AppState, required: Role) -> Result<Actor, StatusCode> {
+    let actor = authenticate(parts, state).await?;
+
+    if actor.role().is_none_or(|role| role < required) {
+        tracing::warn!("{} lacks the {} role", actor, required);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(actor)
+}
+
+/// Resolves the bearer token of a request to a user session or an API key.
+async fn authenticate(parts: &Parts, state: &AppState) -> Result<Actor, StatusCode> {
+    let token = bearer_token(parts).ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if token.starts_with(SESSION_PREFIX) {
+        let (user, _) = session_user(state, token).await?;
+        return Ok(Actor::User(user));
+    }
+
+    let row: Option<(i32, String, String)> = sqlx::query_as(
+        r#"
+        UPDATE api_keys SET last_used_at = NOW()
+        WHERE key_hash = $1 AND revoked_at IS NULL
+        RETURNING id, name, scope
+        "#,
+    )
+    .bind(hash_key(token))
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up API key: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some((id, name, scope)) = row else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let scope: Scope = scope.parse().map_err(|e| {
+        tracing::error!("API key {} has an invalid scope: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Actor::ApiKey(ApiKey { id, name, scope }))
+}
+
+/// Extractor for the user a session token belongs to.
+///
+/// Requests must carry `Authorization: Bearer <session token>` as issued by
+/// `POST /auth/login`. Revoked and expired sessions are rejected.
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub user: User,
+    pub session_id: i32,
+}
+
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = bearer_token(parts).ok_or(StatusCode::UNAUTHORIZED)?;
+        let (user, session_id) = session_user(state, token).await?;
+
+        Ok(CurrentUser { user, session_id })
+    }
+}
+
+async fn session_user(state: &AppState, token: &str) -> Result<(User, i32), StatusCode> {
+    let session_id =
+        verify_session_token(&state.session_secret, token).ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        SELECT u.id, u.email, u.display_name, u.role
+        FROM sessions s
+        JOIN users u ON s.user_id = u.id
+        WHERE s.id = $1 AND s.revoked_at IS NULL AND s.expires_at > NOW()
+        "#,
+    )
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up session {}: {}", session_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    Ok((user, session_id))
+}
+
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+}
+
+/// Issues a signed token for a session row: `ubs_<session id>.<expiry>.<signature>`.
+pub fn sign_session_token(secret: &[u8], session_id: i32, expires_at: DateTime<Utc>) -> String {
+    let payload = format!("{}.{}", session_id, expires_at.timestamp());
+    format!(
+        "{}{}.{}",
+        SESSION_PREFIX,
+        payload,
+        to_hex(&session_mac(secret, &payload).finalize().into_bytes())
+    )
+}
+
+/// Checks a session token's signature and expiry and returns its session id.
+/// Whether the session has been revoked is up to the caller to check.
+pub fn verify_session_token(secret: &[u8], token: &str) -> Option<i32> {
+    let token = token.strip_prefix(SESSION_PREFIX)?;
+    let (payload, signature) = token.rsplit_once('.')?;
+    let (session_id, expires) = payload.split_once('.')?;
+
+    let signature = from_hex(signature)?;
+    session_mac(secret, payload).verify_slice(&signature).ok()?;
+
+    let expires: i64 = expires.parse().ok()?;
+    if expires <= Utc::now().timestamp() {
+        return None;
+    }
+
+    session_id.parse().ok()
+}
+
+fn session_mac(secret: &[u8], payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Generates a random secret for signing session tokens.
+pub fn generate_session_secret() -> Vec<u8> {
+    let mut bytes = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// Generates a new random API key. Only its hash should ever be persisted.
+pub fn generate_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, to_hex(&bytes))
+}
+
+pub fn hash_key(key: &str) -> String {
+    to_hex(&Sha256::digest(key.as_bytes()))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,110 @@
+use color_eyre::eyre::{bail, eyre};
+use sqlx::PgPool;
+
+use crate::auth::{Scope, generate_key, hash_key};
+
+const KEYS_USAGE: &str = "\
+Usage:
+  utilbench-backend keys create <name> [--scope read|write|admin]
+  utilbench-backend keys list
+  utilbench-backend keys revoke <id>";
+
+/// Runs the `keys` subcommand used to manage API keys.
+pub async fn run_keys(pool: &PgPool, args: &[String]) -> color_eyre::Result<()> {
+    match args.first().map(String::as_str) {
+        Some("create") => {
+            let mut name = None;
+            let mut scope = Scope::Read;
+
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--scope" => {
+                        let value = rest.next().ok_or_else(|| eyre!("--scope needs a value"))?;
+                        scope = value.parse().map_err(|e: String| eyre!(e))?;
+                    }
+                    other if name.is_none() => name = Some(other.to_string()),
+                    other => bail!("Unexpected argument '{}'\n\n{}", other, KEYS_USAGE),
+                }
+            }
+
+            let name = name.ok_or_else(|| eyre!("Missing key name\n\n{}", KEYS_USAGE))?;
+            create_key(pool, &name, scope).await
+        }
+        Some("list") => list_keys(pool).await,
+        Some("revoke") => {
+            let id: i32 = args
+                .get(1)
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| eyre!("Missing or invalid key id\n\n{}", KEYS_USAGE))?;
+            revoke_key(pool, id).await
+        }
+        _ => bail!("{}", KEYS_USAGE),
+    }
+}
+
+async fn create_key(pool: &PgPool, name: &str, scope: Scope) -> color_eyre::Result<()> {
+    let key = generate_key();
+    let prefix: String = key.chars().take(11).collect();
+
+    let id: i32 = sqlx::query_scalar(
+        "INSERT INTO api_keys (name, key_prefix, key_hash, scope) VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(name)
+    .bind(&prefix)
+    .bind(hash_key(&key))
+    .bind(scope.as_str())
+    .fetch_one(pool)
+    .await?;
+
+    println!("Created {} key #{} '{}'", scope, id, name);
+    println!("{}", key);
+    println!("Store it now, it cannot be shown again.");
+
+    Ok(())
+}
+
+async fn list_keys(pool: &PgPool) -> color_eyre::Result<()> {
+    let keys: Vec<(i32, String, String, String, Option<String>, bool)> = sqlx::query_as(
+        r#"
+        SELECT id, name, key_prefix, scope, to_char(last_used_at, 'YYYY-MM-DD HH24:MI'), revoked_at IS NOT NULL
+        FROM api_keys
+        ORDER BY id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    println!(
+        "{:>4}  {:<24} {:<12} {:<6} {:<17} status",
+        "id", "name", "prefix", "scope", "last used"
+    );
+    for (id, name, prefix, scope, last_used, revoked) in keys {
+        println!(
+            "{:>4}  {:<24} {:<12} {:<6} {:<17} {}",
+            id,
+            name,
+            format!("{}…", prefix),
+            scope,
+            last_used.unwrap_or_else(|| "never".to_string()),
+            if revoked { "revoked" } else { "active" }
+        );
+    }
+
+    Ok(())
+}
+
+async fn revoke_key(pool: &PgPool, id: i32) -> color_eyre::Result<()> {
+    let result =
+        sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        bail!("No active API key with id {}", id);
+    }
+
+    println!("Revoked key #{}", id);
+    Ok(())
+}
@@ -1,52 +1,15 @@
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
+
 use serde_json::json;
 
-use crate::models::{Category, ComparisonRow, ComparisonValue, Product};
+use crate::models::{
+    BaselineSummary, Category, ComparisonRow, ComparisonValue, ExcludedField, MissingValues,
+    Product, Verdict,
+};
+use crate::stats::{CategoryDistribution, field_value};
 
 pub fn build_comparison_table(category: &Category, products: &[Product]) -> Vec<ComparisonRow> {
-    let mut rows = Vec::new();
-
-    // Add basic info rows
-    rows.push(ComparisonRow {
-        field: "name".to_string(),
-        label: "Product Name".to_string(),
-        unit: "".to_string(),
-        values: products
-            .iter()
-            .map(|p| ComparisonValue {
-                product_id: p.id,
-                product_name: p.name.clone(),
-                value: json!(p.name.clone()),
-            })
-            .collect(),
-    });
-
-    rows.push(ComparisonRow {
-        field: "manufacturer".to_string(),
-        label: "Manufacturer".to_string(),
-        unit: "".to_string(),
-        values: products
-            .iter()
-            .map(|p| ComparisonValue {
-                product_id: p.id,
-                product_name: p.name.clone(),
-                value: json!(p.manufacturer.clone().unwrap_or_default()),
-            })
-            .collect(),
-    });
-
-    rows.push(ComparisonRow {
-        field: "price".to_string(),
-        label: "Price".to_string(),
-        unit: "$".to_string(),
-        values: products
-            .iter()
-            .map(|p| ComparisonValue {
-                product_id: p.id,
-                product_name: p.name.clone(),
-                value: json!(p.price.clone().map(|pr| pr.to_string()).unwrap_or_default()),
-            })
-            .collect(),
-    });
+    let mut rows = basic_rows(products);
 
     // Add specification rows based on schema
     if let Some(schema) = &category.specification_schema
@@ -54,44 +17,454 @@ pub fn build_comparison_table(category: &Category, products: &[Product]) -> Vec<
     {
         for (field_name, field_def) in schema_obj {
             if let Some(field_obj) = field_def.as_object() {
-                let label = field_obj
-                    .get("label")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(field_name)
-                    .to_string();
-                let unit = field_obj
-                    .get("unit")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                let values = products
-                    .iter()
-                    .map(|p| {
-                        let value = p
-                            .specifications
-                            .as_ref()
-                            .and_then(|specs| specs.get(field_name))
-                            .cloned()
-                            .unwrap_or(json!(null));
-
-                        ComparisonValue {
-                            product_id: p.id,
-                            product_name: p.name.clone(),
-                            value,
-                        }
-                    })
-                    .collect();
-
-                rows.push(ComparisonRow {
-                    field: field_name.clone(),
-                    label,
-                    unit,
-                    values,
-                });
+                rows.push(specification_row(field_name, field_obj, products));
             }
         }
     }
 
     rows
 }
+
+/// Builds a comparison table for products from several categories, using only the
+/// specification fields that every category defines with the same type and unit.
+/// Fields that could not be compared are returned alongside the rows.
+pub fn build_common_comparison_table(
+    categories: &[Category],
+    products: &[Product],
+) -> (Vec<ComparisonRow>, Vec<ExcludedField>) {
+    let mut rows = basic_rows(products);
+    let mut excluded: Vec<ExcludedField> = Vec::new();
+
+    let schemas: Vec<(&Category, &serde_json::Map<String, serde_json::Value>)> = categories
+        .iter()
+        .filter_map(|c| {
+            c.specification_schema
+                .as_ref()
+                .and_then(|s| s.as_object())
+                .map(|obj| (c, obj))
+        })
+        .collect();
+
+    let mut field_names: Vec<&String> = schemas.iter().flat_map(|(_, s)| s.keys()).collect();
+    field_names.sort();
+    field_names.dedup();
+
+    for field_name in field_names {
+        let definitions: Vec<(&Category, &serde_json::Map<String, serde_json::Value>)> = schemas
+            .iter()
+            .filter_map(|(c, s)| {
+                s.get(field_name)
+                    .and_then(|d| d.as_object())
+                    .map(|d| (*c, d))
+            })
+            .collect();
+
+        let Some((_, first)) = definitions.first() else {
+            continue;
+        };
+
+        let reason = if definitions.len() < categories.len() {
+            Some("missing")
+        } else if definitions
+            .iter()
+            .any(|(_, d)| d.get("type") != first.get("type"))
+        {
+            Some("type_mismatch")
+        } else if definitions
+            .iter()
+            .any(|(_, d)| d.get("unit") != first.get("unit"))
+        {
+            Some("unit_mismatch")
+        } else {
+            None
+        };
+
+        match reason {
+            None => rows.push(specification_row(field_name, first, products)),
+            Some(reason) => excluded.push(ExcludedField {
+                field: field_name.clone(),
+                label: field_label(field_name, first),
+                categories: definitions.iter().map(|(c, _)| c.slug.clone()).collect(),
+                reason: reason.to_string(),
+            }),
+        }
+    }
+
+    (rows, excluded)
+}
+
+/// Measures every value against the baseline product's: numeric values get
+/// their difference and percentage, and fields with `higher_is_better` a
+/// verdict. Returns how each other product fares against the baseline.
+pub fn apply_baseline(
+    rows: &mut [ComparisonRow],
+    products: &[Product],
+    baseline_id: i32,
+) -> Vec<BaselineSummary> {
+    for row in rows.iter_mut() {
+        let Some(baseline) = row
+            .values
+            .iter()
+            .find(|v| v.product_id == baseline_id)
+            .map(|v| v.value.clone())
+        else {
+            continue;
+        };
+        let baseline_number = numeric_value(&baseline);
+
+        for value in &mut row.values {
+            if let (Some(base), Some(number)) = (baseline_number, numeric_value(&value.value)) {
+                value.delta = Some(round(number - base));
+                value.delta_percent =
+                    (base != 0.0).then(|| round((number - base) / base.abs() * 100.0));
+            }
+
+            if value.product_id != baseline_id
+                && let Some(higher_is_better) = row.higher_is_better
+            {
+                value.verdict = verdict(&value.value, &baseline, higher_is_better);
+            }
+        }
+    }
+
+    products
+        .iter()
+        .filter(|p| p.id != baseline_id)
+        .map(|p| {
+            let verdicts: Vec<Verdict> = rows
+                .iter()
+                .flat_map(|row| &row.values)
+                .filter(|v| v.product_id == p.id)
+                .filter_map(|v| v.verdict)
+                .collect();
+            let count = |verdict: Verdict| verdicts.iter().filter(|v| **v == verdict).count();
+
+            BaselineSummary {
+                product_id: p.id,
+                product_name: p.name.clone(),
+                wins: count(Verdict::Better),
+                losses: count(Verdict::Worse),
+                ties: count(Verdict::Same),
+                price_difference: rows
+                    .iter()
+                    .find(|row| row.field == "price")
+                    .and_then(|row| row.values.iter().find(|v| v.product_id == p.id))
+                    .and_then(|v| v.delta),
+            }
+        })
+        .collect()
+}
+
+/// Ranks each numeric value within its product's whole category, using the
+/// distributions keyed by category id. Prices are ranked by their euro price,
+/// whatever currency the table shows them in.
+pub fn apply_percentiles(
+    rows: &mut [ComparisonRow],
+    products: &[Product],
+    distributions: &HashMap<i32, Arc<CategoryDistribution>>,
+) {
+    for row in rows.iter_mut() {
+        for value in &mut row.values {
+            let Some(product) = products.iter().find(|p| p.id == value.product_id) else {
+                continue;
+            };
+            let number = if row.field == "price" {
+                numeric_value(&field_value(product, "price"))
+            } else {
+                numeric_value(&value.value)
+            };
+            let Some(number) = number else {
+                continue;
+            };
+
+            value.category_percentile = distributions
+                .get(&product.category_id)
+                .and_then(|d| d.percentile_rank(&row.field, number, row.higher_is_better))
+                .map(round);
+        }
+    }
+}
+
+/// Diff view of a comparison table: lists the fields each product is missing
+/// and drops rows where every product has the same value.
+pub fn apply_diff(
+    rows: Vec<ComparisonRow>,
+    products: &[Product],
+) -> (Vec<ComparisonRow>, Vec<MissingValues>) {
+    let missing = products
+        .iter()
+        .map(|p| MissingValues {
+            product_id: p.id,
+            product_name: p.name.clone(),
+            fields: rows
+                .iter()
+                .filter(|row| {
+                    row.values
+                        .iter()
+                        .any(|v| v.product_id == p.id && is_missing(&v.value))
+                })
+                .map(|row| row.field.clone())
+                .collect(),
+        })
+        .filter(|m| !m.fields.is_empty())
+        .collect();
+
+    let rows = rows.into_iter().filter(|row| !all_equal(row)).collect();
+
+    (rows, missing)
+}
+
+/// Reads a value as a number. Specifications often store numbers as strings
+/// like "3.0", and prices are serialized as decimal strings.
+pub fn numeric_value(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse().ok().filter(|n: &f64| n.is_finite()),
+        _ => None,
+    }
+}
+
+/// Compares numbers by value and booleans with true as the higher one.
+fn verdict(
+    value: &serde_json::Value,
+    baseline: &serde_json::Value,
+    higher_is_better: bool,
+) -> Option<Verdict> {
+    let ordering = match (numeric_value(value), numeric_value(baseline)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b)?,
+        _ => value.as_bool()?.cmp(&baseline.as_bool()?),
+    };
+
+    Some(match (ordering, higher_is_better) {
+        (Ordering::Equal, _) => Verdict::Same,
+        (Ordering::Greater, true) | (Ordering::Less, false) => Verdict::Better,
+        _ => Verdict::Worse,
+    })
+}
+
+fn is_missing(value: &serde_json::Value) -> bool {
+    value.is_null() || value.as_str().is_some_and(|s| s.is_empty())
+}
+
+/// Whether every product has the same value, comparing numbers by value so
+/// "3.0" and 3 are equal.
+fn all_equal(row: &ComparisonRow) -> bool {
+    let Some(first) = row.values.first() else {
+        return true;
+    };
+
+    row.values.iter().all(
+        |v| match (numeric_value(&first.value), numeric_value(&v.value)) {
+            (Some(a), Some(b)) => a == b,
+            _ => v.value == first.value,
+        },
+    )
+}
+
+pub(crate) fn round(n: f64) -> f64 {
+    (n * 100.0).round() / 100.0
+}
+
+/// Products are priced at their lowest in-stock offer. When those offers are
+/// in different currencies the prices are shown with their currency, as text,
+/// so they aren't measured against each other. Percentiles still rank them,
+/// by their euro prices, with the cheapest best.
+fn basic_rows(products: &[Product]) -> Vec<ComparisonRow> {
+    let mut currencies = products
+        .iter()
+        .filter(|p| p.price.is_some())
+        .map(|p| p.currency.as_deref().unwrap_or_default());
+    let single_currency = match currencies.next() {
+        None => Some(""),
+        Some(first) if currencies.all(|c| c == first) => Some(first),
+        Some(_) => None,
+    };
+
+    vec![
+        ComparisonRow {
+            field: "name".to_string(),
+            label: "Product Name".to_string(),
+            unit: "".to_string(),
+            higher_is_better: None,
+            values: products
+                .iter()
+                .map(|p| ComparisonValue {
+                    product_id: p.id,
+                    product_name: p.name.clone(),
+                    value: json!(p.name.clone()),
+                    delta: None,
+                    delta_percent: None,
+                    verdict: None,
+                    category_percentile: None,
+                })
+                .collect(),
+        },
+        ComparisonRow {
+            field: "manufacturer".to_string(),
+            label: "Manufacturer".to_string(),
+            unit: "".to_string(),
+            higher_is_better: None,
+            values: products
+                .iter()
+                .map(|p| ComparisonValue {
+                    product_id: p.id,
+                    product_name: p.name.clone(),
+                    value: json!(p.manufacturer.clone().unwrap_or_default()),
+                    delta: None,
+                    delta_percent: None,
+                    verdict: None,
+                    category_percentile: None,
+                })
+                .collect(),
+        },
+        ComparisonRow {
+            field: "price".to_string(),
+            label: "Price".to_string(),
+            unit: single_currency.unwrap_or_default().to_string(),
+            higher_is_better: Some(false),
+            values: products
+                .iter()
+                .map(|p| {
+                    let price = p.price.as_ref().map(|pr| match single_currency {
+                        Some(_) => pr.to_string(),
+                        None => format!("{} {}", pr, p.currency.as_deref().unwrap_or_default()),
+                    });
+                    ComparisonValue {
+                        product_id: p.id,
+                        product_name: p.name.clone(),
+                        value: json!(price.unwrap_or_default()),
+                        delta: None,
+                        delta_percent: None,
+                        verdict: None,
+                        category_percentile: None,
+                    }
+                })
+                .collect(),
+        },
+        ComparisonRow {
+            field: "offer_count".to_string(),
+            label: "Offers".to_string(),
+            unit: "".to_string(),
+            higher_is_better: None,
+            values: products
+                .iter()
+                .map(|p| ComparisonValue {
+                    product_id: p.id,
+                    product_name: p.name.clone(),
+                    value: json!(p.offer_count),
+                    delta: None,
+                    delta_percent: None,
+                    verdict: None,
+                    category_percentile: None,
+                })
+                .collect(),
+        },
+    ]
+}
+
+fn specification_row(
+    field_name: &str,
+    field_obj: &serde_json::Map<String, serde_json::Value>,
+    products: &[Product],
+) -> ComparisonRow {
+    let unit = field_obj
+        .get("unit")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let values = products
+        .iter()
+        .map(|p| {
+            let value = p
+                .specifications
+                .as_ref()
+                .and_then(|specs| specs.get(field_name))
+                .cloned()
+                .unwrap_or(json!(null));
+
+            ComparisonValue {
+                product_id: p.id,
+                product_name: p.name.clone(),
+                value,
+                delta: None,
+                delta_percent: None,
+                verdict: None,
+                category_percentile: None,
+            }
+        })
+        .collect();
+
+    ComparisonRow {
+        field: field_name.to_string(),
+        label: field_label(field_name, field_obj),
+        unit,
+        higher_is_better: field_obj.get("higher_is_better").and_then(|v| v.as_bool()),
+        values,
+    }
+}
+
+fn field_label(field_name: &str, field_obj: &serde_json::Map<String, serde_json::Value>) -> String {
+    field_obj
+        .get("label")
+        .and_then(|v| v.as_str())
+        .unwrap_or(field_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::*;
+    use crate::models::Category;
+
+    fn product(id: i32, price: &str, currency: &str, price_eur: &str) -> Product {
+        Product {
+            id,
+            category_id: 1,
+            category_name: "CPUs".to_string(),
+            name: format!("CPU {}", id),
+            slug: format!("cpu-{}", id),
+            manufacturer: None,
+            model: None,
+            specifications: None,
+            price: Some(price.parse::<BigDecimal>().unwrap()),
+            currency: Some(currency.to_string()),
+            price_eur: Some(price_eur.parse::<BigDecimal>().unwrap()),
+            offer_count: 1,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn mixed_currency_prices_rank_cheapest_first() {
+        let category = Category {
+            id: 1,
+            name: "CPUs".to_string(),
+            slug: "cpus".to_string(),
+            description: None,
+            specification_schema: None,
+        };
+        // The CZK price is the largest number but the cheapest in euros
+        let products = vec![
+            product(1, "12500", "CZK", "500"),
+            product(2, "600", "EUR", "600"),
+            product(3, "700", "USD", "650"),
+        ];
+        let distributions =
+            HashMap::from([(1, Arc::new(CategoryDistribution::new(&category, &products)))]);
+
+        let mut rows = basic_rows(&products);
+        apply_percentiles(&mut rows, &products, &distributions);
+
+        let price = rows.iter().find(|row| row.field == "price").unwrap();
+        assert_eq!(price.higher_is_better, Some(false));
+        let percentiles: Vec<f64> = price
+            .values
+            .iter()
+            .map(|v| v.category_percentile.unwrap())
+            .collect();
+        assert!(percentiles[0] > percentiles[1] && percentiles[1] > percentiles[2]);
+    }
+}
@@ -0,0 +1,372 @@
+use serde_json::json;
+
+use crate::{
+    compare::{numeric_value, round},
+    models::{Category, Product},
+    stats::field_value,
+};
+
+/// Arithmetic over a product's fields, as used by computed specification
+/// fields: numbers, field names, `+ - * /`, unary minus and parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Field(String),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// Deepest an expression tree may be, counting parentheses, unary minus and
+/// chained operators, so parsing, evaluating and dropping it can't overflow
+/// the stack.
+const MAX_DEPTH: usize = 64;
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Expression, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expression = parser.sum()?;
+
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expression),
+            Some(token) => Err(format!("unexpected '{}'", token)),
+        }
+    }
+
+    /// Names of the fields the expression reads.
+    pub fn fields(&self) -> Vec<&str> {
+        match self {
+            Expression::Number(_) => Vec::new(),
+            Expression::Field(name) => vec![name.as_str()],
+            Expression::Negate(inner) => inner.fields(),
+            Expression::Binary(left, _, right) => {
+                let mut fields = left.fields();
+                fields.extend(right.fields());
+                fields
+            }
+        }
+    }
+
+    /// `None` when a field has no numeric value, on division by zero or when
+    /// the result is not a finite number.
+    pub fn evaluate(&self, value: &impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        let result = match self {
+            Expression::Number(n) => *n,
+            Expression::Field(name) => value(name)?,
+            Expression::Negate(inner) => -inner.evaluate(value)?,
+            Expression::Binary(left, operator, right) => {
+                let (left, right) = (left.evaluate(value)?, right.evaluate(value)?);
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide if right == 0.0 => return None,
+                    Operator::Divide => left / right,
+                }
+            }
+        };
+
+        result.is_finite().then_some(result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Field(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Field(name) => write!(f, "{}", name),
+            Token::Operator(c) => write!(f, "{}", c),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '\t' => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '/' => {
+                tokens.push(Token::Operator(c));
+                chars.next();
+            }
+            '(' => {
+                tokens.push(Token::Open);
+                chars.next();
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut number = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '.') {
+                    number.push(d);
+                    chars.next();
+                }
+                let n = number
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", number))?;
+                tokens.push(Token::Number(n));
+            }
+            'a'..='z' | '_' => {
+                let mut name = String::new();
+                while let Some(&d) = chars
+                    .peek()
+                    .filter(|d| d.is_ascii_lowercase() || d.is_ascii_digit() || **d == '_')
+                {
+                    name.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Field(name));
+            }
+            _ => return Err(format!("unexpected character '{}'", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize, // depth of the tree above the token being parsed
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_operator(&self, operators: &[char]) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Operator(c)) if operators.contains(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expression, String> {
+        let depth = self.depth;
+        let mut left = self.product()?;
+        while let Some(c) = self.peek_operator(&['+', '-']) {
+            self.pos += 1;
+            let operator = if c == '+' {
+                Operator::Add
+            } else {
+                Operator::Subtract
+            };
+            let right = self.nested(Self::product)?;
+            left = Expression::Binary(Box::new(left), operator, Box::new(right));
+            // Each operator of a chain nests the ones before it a level deeper
+            self.depth += 1;
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expression, String> {
+        let depth = self.depth;
+        let mut left = self.unary()?;
+        while let Some(c) = self.peek_operator(&['*', '/']) {
+            self.pos += 1;
+            let operator = if c == '*' {
+                Operator::Multiply
+            } else {
+                Operator::Divide
+            };
+            let right = self.nested(Self::unary)?;
+            left = Expression::Binary(Box::new(left), operator, Box::new(right));
+            self.depth += 1;
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Operator('-')) => {
+                let inner = self.nested(Self::unary)?;
+                Ok(Expression::Negate(Box::new(inner)))
+            }
+            Some(Token::Number(n)) => Ok(Expression::Number(n)),
+            Some(Token::Field(name)) => Ok(Expression::Field(name)),
+            Some(Token::Open) => {
+                let inner = self.nested(Self::sum)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected '{}'", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Expression, String>,
+    ) -> Result<Expression, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(format!("more than {} levels deep", MAX_DEPTH));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+}
+
+/// The category's computed fields with their parsed expressions. Fields whose
+/// expression does not parse are left out; schemas are validated on write.
+pub fn computed_fields(category: &Category) -> Vec<(String, Expression)> {
+    let Some(schema) = category
+        .specification_schema
+        .as_ref()
+        .and_then(|s| s.as_object())
+    else {
+        return Vec::new();
+    };
+
+    schema
+        .iter()
+        .filter_map(|(field, def)| {
+            let expression = def.get("expression")?.as_str()?;
+            Expression::parse(expression)
+                .ok()
+                .map(|e| (field.clone(), e))
+        })
+        .collect()
+}
+
+/// Adds the values of the category's computed fields to the specifications
+/// of its products, `null` when an input is missing. Products from other
+/// categories are left alone.
+pub fn apply_computed(category: &Category, products: &mut [Product]) {
+    let fields = computed_fields(category);
+    if fields.is_empty() {
+        return;
+    }
+
+    for product in products.iter_mut().filter(|p| p.category_id == category.id) {
+        let values: Vec<(String, serde_json::Value)> = fields
+            .iter()
+            .map(|(field, expression)| {
+                let value = expression
+                    .evaluate(&|name| numeric_value(&field_value(product, name)))
+                    .map(round);
+                (field.clone(), json!(value))
+            })
+            .collect();
+
+        let specifications = product.specifications.get_or_insert_with(|| json!({}));
+        if let Some(specs) = specifications.as_object_mut() {
+            specs.extend(values);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(input: &str) -> Option<f64> {
+        let values = |name: &str| match name {
+            "price" => Some(500.0),
+            "cores" => Some(16.0),
+            "zero" => Some(0.0),
+            _ => None,
+        };
+        Expression::parse(input).unwrap().evaluate(&values)
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        assert_eq!(evaluate("2 + 3 * 4"), Some(14.0));
+        assert_eq!(evaluate("(2 + 3) * 4"), Some(20.0));
+        assert_eq!(evaluate("price / cores * 2"), Some(62.5));
+        assert_eq!(evaluate("10 - 4 - 3"), Some(3.0));
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(evaluate("-cores"), Some(-16.0));
+        assert_eq!(evaluate("2 * -3"), Some(-6.0));
+        assert_eq!(evaluate("--4"), Some(4.0));
+        assert_eq!(evaluate("-(1 + 2) * 3"), Some(-9.0));
+    }
+
+    #[test]
+    fn division_by_zero_has_no_value() {
+        assert_eq!(evaluate("price / zero"), None);
+        assert_eq!(evaluate("1 / (cores - 16)"), None);
+    }
+
+    #[test]
+    fn missing_field_has_no_value() {
+        assert_eq!(evaluate("price / tdp"), None);
+    }
+
+    #[test]
+    fn lists_fields() {
+        let expression = Expression::parse("(price + 10) / cores - price").unwrap();
+        assert_eq!(expression.fields(), vec!["price", "cores", "price"]);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for input in [
+            "",
+            "price +",
+            "(price / cores",
+            "price cores",
+            "price / cores)",
+            "1..2",
+            "Price",
+            "price % 2",
+        ] {
+            assert!(Expression::parse(input).is_err(), "{} parsed", input);
+        }
+    }
+
+    #[test]
+    fn rejects_deep_expressions() {
+        let nested = |depth: usize| format!("{}cores{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(evaluate(&nested(MAX_DEPTH)), Some(16.0));
+        assert!(Expression::parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(Expression::parse(&nested(100_000)).is_err());
+        assert!(Expression::parse(&format!("{}1", "-".repeat(100_000))).is_err());
+
+        let chain = |terms: usize| vec!["1"; terms].join(" + ");
+        assert_eq!(evaluate(&chain(MAX_DEPTH)), Some(MAX_DEPTH as f64));
+        assert!(Expression::parse(&chain(100_000)).is_err());
+        assert!(Expression::parse(&vec!["cores"; 100_000].join(" * ")).is_err());
+    }
+}
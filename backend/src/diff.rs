@@ -0,0 +1,122 @@
+use serde_json::json;
+
+use crate::models::{FieldDifference, Product};
+
+/// Lists the fields whose values differ between two products: the basic product
+/// columns first, then every specification key present on either side.
+pub fn product_differences(left: &Product, right: &Product) -> Vec<FieldDifference> {
+    let mut differences = Vec::new();
+
+    let basic = [
+        ("name", json!(left.name), json!(right.name)),
+        (
+            "manufacturer",
+            json!(left.manufacturer),
+            json!(right.manufacturer),
+        ),
+        ("model", json!(left.model), json!(right.model)),
+        (
+            "price",
+            json!(left.price.as_ref().map(|p| p.to_string())),
+            json!(right.price.as_ref().map(|p| p.to_string())),
+        ),
+    ];
+
+    for (field, left_value, right_value) in basic {
+        if left_value != right_value {
+            differences.push(FieldDifference {
+                field: field.to_string(),
+                before: left_value,
+                after: right_value,
+            });
+        }
+    }
+
+    let empty = serde_json::Map::new();
+    let left_specs = left
+        .specifications
+        .as_ref()
+        .and_then(|s| s.as_object())
+        .unwrap_or(&empty);
+    let right_specs = right
+        .specifications
+        .as_ref()
+        .and_then(|s| s.as_object())
+        .unwrap_or(&empty);
+
+    let mut keys: Vec<&String> = left_specs.keys().chain(right_specs.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let left_value = left_specs.get(key).cloned().unwrap_or(json!(null));
+        let right_value = right_specs.get(key).cloned().unwrap_or(json!(null));
+
+        if left_value != right_value {
+            differences.push(FieldDifference {
+                field: format!("specifications.{}", key),
+                before: left_value,
+                after: right_value,
+            });
+        }
+    }
+
+    differences
+}
+
+/// Lists the differences between two JSON documents. Objects are compared key by
+/// key with dotted paths (`specifications.cores`), anything else as a whole.
+pub fn json_differences(
+    before: &serde_json::Value,
+    after: &serde_json::Value,
+) -> Vec<FieldDifference> {
+    let mut differences = Vec::new();
+    collect_json_differences("", before, after, &mut differences);
+    differences
+}
+
+fn collect_json_differences(
+    path: &str,
+    before: &serde_json::Value,
+    after: &serde_json::Value,
+    differences: &mut Vec<FieldDifference>,
+) {
+    // A missing object is compared as an empty one, so creations list every field
+    let empty = serde_json::Value::Object(serde_json::Map::new());
+    let before = if before.is_null() && after.is_object() {
+        &empty
+    } else {
+        before
+    };
+    let after = if after.is_null() && before.is_object() {
+        &empty
+    } else {
+        after
+    };
+
+    if let (Some(before_obj), Some(after_obj)) = (before.as_object(), after.as_object()) {
+        let mut keys: Vec<&String> = before_obj.keys().chain(after_obj.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        for key in keys {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            collect_json_differences(
+                &child,
+                before_obj.get(key).unwrap_or(&serde_json::Value::Null),
+                after_obj.get(key).unwrap_or(&serde_json::Value::Null),
+                differences,
+            );
+        }
+    } else if before != after {
+        differences.push(FieldDifference {
+            field: path.to_string(),
+            before: before.clone(),
+            after: after.clone(),
+        });
+    }
+}
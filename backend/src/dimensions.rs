@@ -0,0 +1,427 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compare::round,
+    models::{Category, ComparisonRow, Product},
+};
+
+/// Measurements of a product as stored in its `dimensions` fields.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Dimensions {
+    pub width: f64,
+    pub height: f64,
+    pub depth: f64,
+    pub unit: LengthUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    Mm,
+    #[default]
+    Cm,
+    In,
+}
+
+impl LengthUnit {
+    pub fn parse(unit: &str) -> Option<LengthUnit> {
+        match unit.trim().to_lowercase().as_str() {
+            "mm" | "millimeter" | "millimeters" | "millimetre" | "millimetres" => {
+                Some(LengthUnit::Mm)
+            }
+            "cm" | "centimeter" | "centimeters" | "centimetre" | "centimetres" => {
+                Some(LengthUnit::Cm)
+            }
+            "in" | "inch" | "inches" | "\"" => Some(LengthUnit::In),
+            _ => None,
+        }
+    }
+
+    fn millimeters(self) -> f64 {
+        match self {
+            LengthUnit::Mm => 1.0,
+            LengthUnit::Cm => 10.0,
+            LengthUnit::In => 25.4,
+        }
+    }
+
+    pub fn convert(self, value: f64, to: LengthUnit) -> f64 {
+        value * self.millimeters() / to.millimeters()
+    }
+}
+
+/// Order of the measurements in an unlabelled string such as
+/// `"14.0 x 23.9 x 19.4"`, as letters `w`, `h` and `d`.
+pub const DEFAULT_ORDER: &str = "whd";
+
+pub fn valid_order(order: &str) -> bool {
+    let mut letters: Vec<char> = order.chars().collect();
+    letters.sort_unstable();
+    letters == ['d', 'h', 'w']
+}
+
+impl Dimensions {
+    /// Parses `W x H x D` strings. Measurements may be labelled
+    /// (`24"W x 34"H x 24"D`, `60 cm (W) x 85 cm (H) x 60 cm (D)`), otherwise
+    /// they are read in `order`. A unit anywhere in the string overrides
+    /// `default_unit`.
+    pub fn parse(input: &str, order: &str, default_unit: LengthUnit) -> Result<Dimensions, String> {
+        let parts: Vec<&str> = input.split(['x', 'X', '×', '*']).map(str::trim).collect();
+        if parts.len() != 3 {
+            return Err(format!(
+                "expected three measurements separated by 'x', got '{}'",
+                input
+            ));
+        }
+
+        let mut unit = None;
+        let mut values = [None; 3]; // width, height, depth
+        for (i, part) in parts.iter().enumerate() {
+            let number_end = part
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(part.len());
+            let number: f64 = part[..number_end]
+                .parse()
+                .map_err(|_| format!("'{}' is not a measurement", part))?;
+            if !number.is_finite() || number <= 0.0 {
+                return Err(format!("'{}' is not a positive measurement", part));
+            }
+
+            // A trailing W, H or D (or "(W)") labels the measurement, unless
+            // it is part of a unit such as "inch"
+            let mut rest = part[number_end..].trim();
+            let mut label = None;
+            if rest.is_ascii() && !rest.is_empty() && LengthUnit::parse(rest).is_none() {
+                let lower = rest.to_lowercase();
+                for letter in ['w', 'h', 'd'] {
+                    let stripped = lower
+                        .strip_suffix(&format!("({})", letter))
+                        .or_else(|| lower.strip_suffix(letter));
+                    if let Some(stripped) = stripped {
+                        rest = rest[..stripped.len()].trim();
+                        label = Some(letter);
+                        break;
+                    }
+                }
+            }
+
+            if !rest.is_empty() {
+                let part_unit =
+                    LengthUnit::parse(rest).ok_or_else(|| format!("unknown unit '{}'", rest))?;
+                if unit.is_some_and(|u| u != part_unit) {
+                    return Err("measurements use different units".to_string());
+                }
+                unit = Some(part_unit);
+            }
+
+            let letter = match label {
+                Some(letter) => letter,
+                None => order.chars().nth(i).unwrap_or('?'),
+            };
+            let slot = match letter {
+                'w' => 0,
+                'h' => 1,
+                'd' => 2,
+                _ => return Err(format!("invalid dimension order '{}'", order)),
+            };
+            if values[slot].replace(number).is_some() {
+                return Err(format!("'{}' is given twice", letter));
+            }
+        }
+
+        match values {
+            [Some(width), Some(height), Some(depth)] => Ok(Dimensions {
+                width,
+                height,
+                depth,
+                unit: unit.unwrap_or(default_unit),
+            }),
+            _ => Err("width, height and depth are all required".to_string()),
+        }
+    }
+
+    /// Reads a stored value: the structured form, or a string written before
+    /// dimensions were parsed on write.
+    pub fn from_value(
+        value: &serde_json::Value,
+        order: &str,
+        default_unit: LengthUnit,
+    ) -> Option<Dimensions> {
+        match value {
+            serde_json::Value::String(s) => Dimensions::parse(s, order, default_unit).ok(),
+            serde_json::Value::Object(_) => serde_json::from_value(value.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn in_unit(self, unit: LengthUnit) -> Dimensions {
+        Dimensions {
+            width: self.unit.convert(self.width, unit),
+            height: self.unit.convert(self.height, unit),
+            depth: self.unit.convert(self.depth, unit),
+            unit,
+        }
+    }
+}
+
+/// Maximum measurements of the space a product must fit into.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Space {
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub depth: Option<f64>,
+    pub unit: LengthUnit,
+    pub clearance: f64, // free space needed on top of each measurement
+}
+
+impl Space {
+    /// Products are not rotated: width must fit width, and so on.
+    pub fn fits(&self, dimensions: Dimensions) -> bool {
+        let d = dimensions.in_unit(self.unit);
+        let fits = |size: f64, max: Option<f64>| max.is_none_or(|max| size + self.clearance <= max);
+
+        fits(d.width, self.width) && fits(d.height, self.height) && fits(d.depth, self.depth)
+    }
+}
+
+/// The category's fields of type `dimensions`, with the order and unit their
+/// unlabelled strings are read in.
+pub fn dimension_fields(category: &Category) -> Vec<(String, String, LengthUnit)> {
+    let Some(schema) = category
+        .specification_schema
+        .as_ref()
+        .and_then(|s| s.as_object())
+    else {
+        return Vec::new();
+    };
+
+    schema
+        .iter()
+        .filter(|(_, def)| def.get("type").and_then(|t| t.as_str()) == Some("dimensions"))
+        .map(|(field, def)| {
+            let order = def
+                .get("order")
+                .and_then(|o| o.as_str())
+                .unwrap_or(DEFAULT_ORDER);
+            let unit = def
+                .get("unit")
+                .and_then(|u| u.as_str())
+                .and_then(LengthUnit::parse)
+                .unwrap_or_default();
+            (field.clone(), order.to_string(), unit)
+        })
+        .collect()
+}
+
+/// Replaces the string values of the category's dimensions fields with the
+/// structured form. Fails on values that can't be parsed.
+pub fn parse_specifications(
+    category: &Category,
+    specifications: &mut serde_json::Value,
+) -> Result<(), String> {
+    let Some(specs) = specifications.as_object_mut() else {
+        return Ok(());
+    };
+
+    for (field, order, unit) in dimension_fields(category) {
+        let Some(value) = specs.get_mut(&field) else {
+            continue;
+        };
+
+        let dimensions = match value {
+            serde_json::Value::Null => continue,
+            serde_json::Value::String(s) => {
+                Dimensions::parse(s, &order, unit).map_err(|e| format!("{}: {}", field, e))?
+            }
+            _ => serde_json::from_value::<Dimensions>(value.clone())
+                .map_err(|e| format!("{}: {}", field, e))?,
+        };
+
+        let positive = |n: f64| n.is_finite() && n > 0.0;
+        if !(positive(dimensions.width)
+            && positive(dimensions.height)
+            && positive(dimensions.depth))
+        {
+            return Err(format!("{}: measurements must be positive", field));
+        }
+
+        *value = serde_json::to_value(dimensions).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Whether any of the product's dimensions fields fits the space.
+pub fn product_fits(category: &Category, product: &Product, space: &Space) -> bool {
+    dimension_fields(category)
+        .iter()
+        .any(|(field, order, unit)| {
+            product
+                .specifications
+                .as_ref()
+                .and_then(|specs| specs.get(field))
+                .and_then(|value| Dimensions::from_value(value, order, *unit))
+                .is_some_and(|dimensions| space.fits(dimensions))
+        })
+}
+
+/// Shows the category's dimensions fields in `unit` in a comparison table.
+/// Values of products from other categories are left alone.
+pub fn convert_rows(
+    category: &Category,
+    products: &[Product],
+    rows: &mut [ComparisonRow],
+    unit: LengthUnit,
+) {
+    for (field, order, stored_unit) in dimension_fields(category) {
+        let Some(row) = rows.iter_mut().find(|row| row.field == field) else {
+            continue;
+        };
+
+        for value in &mut row.values {
+            let in_category = products
+                .iter()
+                .any(|p| p.id == value.product_id && p.category_id == category.id);
+            if !in_category {
+                continue;
+            }
+            if let Some(dimensions) = Dimensions::from_value(&value.value, &order, stored_unit) {
+                let d = dimensions.in_unit(unit);
+                value.value = serde_json::to_value(Dimensions {
+                    width: round(d.width),
+                    height: round(d.height),
+                    depth: round(d.depth),
+                    unit,
+                })
+                .unwrap_or_default();
+            }
+        }
+        row.unit = match unit {
+            LengthUnit::Mm => "mm",
+            LengthUnit::Cm => "cm",
+            LengthUnit::In => "in",
+        }
+        .to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn dimensions(width: f64, height: f64, depth: f64, unit: LengthUnit) -> Dimensions {
+        Dimensions {
+            width,
+            height,
+            depth,
+            unit,
+        }
+    }
+
+    #[test]
+    fn unlabelled_measurements_follow_the_order() {
+        assert_eq!(
+            Dimensions::parse("60 x 85 x 55", DEFAULT_ORDER, LengthUnit::Cm),
+            Ok(dimensions(60.0, 85.0, 55.0, LengthUnit::Cm))
+        );
+        assert_eq!(
+            Dimensions::parse("85 x 60 x 55", "hwd", LengthUnit::Cm),
+            Ok(dimensions(60.0, 85.0, 55.0, LengthUnit::Cm))
+        );
+    }
+
+    #[test]
+    fn labels_override_the_order() {
+        assert_eq!(
+            Dimensions::parse("34\"H x 24\"W x 24\"D", DEFAULT_ORDER, LengthUnit::Cm),
+            Ok(dimensions(24.0, 34.0, 24.0, LengthUnit::In))
+        );
+        assert_eq!(
+            Dimensions::parse("60 cm (W) x 55 cm (D) x 85 cm (H)", "hwd", LengthUnit::Mm),
+            Ok(dimensions(60.0, 85.0, 55.0, LengthUnit::Cm))
+        );
+    }
+
+    #[test]
+    fn inch_is_a_unit_not_a_height_label() {
+        assert_eq!(
+            Dimensions::parse("20 inch x 12 inch x 16 inch", DEFAULT_ORDER, LengthUnit::Cm),
+            Ok(dimensions(20.0, 12.0, 16.0, LengthUnit::In))
+        );
+        assert_eq!(
+            Dimensions::parse(
+                "12 inch h x 20 inch w x 16 inch d",
+                DEFAULT_ORDER,
+                LengthUnit::Cm
+            ),
+            Ok(dimensions(20.0, 12.0, 16.0, LengthUnit::In))
+        );
+    }
+
+    #[test]
+    fn unit_on_one_measurement_applies_to_all() {
+        assert_eq!(
+            Dimensions::parse("600 x 850 x 550 mm", DEFAULT_ORDER, LengthUnit::Cm),
+            Ok(dimensions(600.0, 850.0, 550.0, LengthUnit::Mm))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_measurements() {
+        for input in [
+            "60 x 85",
+            "60 x 85 x 55 x 10",
+            "60 cm x 85 in x 55 cm",
+            "60 x 85 x 55 furlongs",
+            "60W x 85W x 55D",
+            "0 x 85 x 55",
+            "wide x 85 x 55",
+        ] {
+            assert!(
+                Dimensions::parse(input, DEFAULT_ORDER, LengthUnit::Cm).is_err(),
+                "{} parsed",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn converts_units() {
+        let d = dimensions(10.0, 20.0, 30.0, LengthUnit::In).in_unit(LengthUnit::Cm);
+        assert_eq!(d.unit, LengthUnit::Cm);
+        assert!((d.width - 25.4).abs() < 1e-9);
+        assert!((d.depth - 76.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parse_specifications_structures_dimensions_fields() {
+        let category = Category {
+            id: 1,
+            name: "Dishwashers".to_string(),
+            slug: "dishwashers".to_string(),
+            description: None,
+            specification_schema: Some(json!({
+                "dimensions": {"type": "dimensions", "unit": "cm", "order": "hwd"},
+                "noise": {"type": "number"}
+            })),
+        };
+
+        let mut specifications = json!({"dimensions": "85 x 60 x 55", "noise": 44});
+        parse_specifications(&category, &mut specifications).unwrap();
+        assert_eq!(
+            specifications,
+            json!({
+                "dimensions": {"width": 60.0, "height": 85.0, "depth": 55.0, "unit": "cm"},
+                "noise": 44
+            })
+        );
+
+        let mut specifications = json!({"dimensions": "85 x 60"});
+        assert!(parse_specifications(&category, &mut specifications).is_err());
+        let mut specifications =
+            json!({"dimensions": {"width": -1.0, "height": 85.0, "depth": 55.0, "unit": "cm"}});
+        assert!(parse_specifications(&category, &mut specifications).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use crate::models::{DuplicateReason, Offer, Product};
+
+/// Minimum name similarity for two products to be reported as duplicates.
+pub const DUPLICATE_THRESHOLD: f64 = 0.6;
+
+/// Lowercase alphanumeric tokens of a product name, without the manufacturer,
+/// so "Intel Core i9-13900K" and "Core i9 13900K" normalize the same.
+pub fn normalize_name(name: &str, manufacturer: Option<&str>) -> String {
+    let manufacturer = manufacturer.map(normalize_identifier);
+
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .filter(|t| manufacturer.as_deref() != Some(t.as_str()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Trigram similarity in the style of pg_trgm: the share of trigrams, taken
+/// from each word padded with spaces, the two strings have in common.
+pub fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (trigrams(a), trigrams(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Whether two products look like the same item, and how sure we are.
+///
+/// Matching manufacturer and model is conclusive. Otherwise names must be
+/// similar, and model numbers in them must not contradict each other, so
+/// "Ryzen 9 7950X" is not a duplicate of "Ryzen 9 7900X".
+pub fn duplicate_match(a: &Product, b: &Product) -> Option<(f64, DuplicateReason)> {
+    let manufacturer_a = a.manufacturer.as_deref().map(normalize_identifier);
+    let manufacturer_b = b.manufacturer.as_deref().map(normalize_identifier);
+    if let (Some(ma), Some(mb)) = (&manufacturer_a, &manufacturer_b)
+        && ma != mb
+    {
+        return None;
+    }
+
+    let model_a = a.model.as_deref().map(normalize_identifier);
+    let model_b = b.model.as_deref().map(normalize_identifier);
+    if let (Some(ma), Some(mb)) = (&model_a, &model_b)
+        && !ma.is_empty()
+    {
+        if ma == mb {
+            return Some((1.0, DuplicateReason::ManufacturerModel));
+        }
+        return None;
+    }
+
+    let name_a = normalize_name(&a.name, a.manufacturer.as_deref());
+    let name_b = normalize_name(&b.name, b.manufacturer.as_deref());
+
+    let numbers_a = numeric_tokens(&name_a);
+    let numbers_b = numeric_tokens(&name_b);
+    if !numbers_a.is_subset(&numbers_b) && !numbers_b.is_subset(&numbers_a) {
+        return None;
+    }
+
+    let similarity = trigram_similarity(&name_a, &name_b);
+    (similarity >= DUPLICATE_THRESHOLD).then_some((similarity, DuplicateReason::Name))
+}
+
+/// Splits a duplicate's offers on merge into the ones the surviving product
+/// takes over and the ones from retailers it already has an offer from,
+/// which are dropped as the survivor's own offer wins.
+pub fn merge_offers<'a>(
+    survivor: &[Offer],
+    duplicate: &'a [Offer],
+) -> (Vec<&'a Offer>, Vec<&'a Offer>) {
+    let retailers: HashSet<String> = survivor.iter().map(|o| o.retailer.to_lowercase()).collect();
+    duplicate
+        .iter()
+        .partition(|o| !retailers.contains(&o.retailer.to_lowercase()))
+}
+
+/// Lowercase alphanumerics only, so "i9-13900K" and "I9 13900k" compare equal.
+fn normalize_identifier(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn numeric_tokens(normalized: &str) -> HashSet<&str> {
+    normalized
+        .split(' ')
+        .filter(|t| t.chars().any(|c| c.is_ascii_digit()))
+        .collect()
+}
+
+fn trigrams(s: &str) -> HashSet<String> {
+    let mut trigrams = HashSet::new();
+    for word in s.split_whitespace() {
+        let padded: Vec<char> = format!("  {} ", word.to_lowercase()).chars().collect();
+        for window in padded.windows(3) {
+            trigrams.insert(window.iter().collect());
+        }
+    }
+    trigrams
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer(id: i32, product_id: i32, retailer: &str) -> Offer {
+        Offer {
+            id,
+            product_id,
+            retailer: retailer.to_string(),
+            price: 10000.into(),
+            currency: "EUR".to_string(),
+            url: None,
+            stock_status: "in_stock".to_string(),
+            last_seen_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn merge_drops_offers_from_retailers_the_survivor_has() {
+        let survivor = [offer(1, 1, "Alza")];
+        let duplicate = [offer(2, 2, "alza"), offer(3, 2, "CZC")];
+
+        let (moved, dropped) = merge_offers(&survivor, &duplicate);
+        assert_eq!(moved.iter().map(|o| o.id).collect::<Vec<_>>(), [3]);
+        assert_eq!(dropped.iter().map(|o| o.id).collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn merge_moves_every_offer_when_retailers_differ() {
+        let duplicate = [offer(2, 2, "Alza")];
+
+        let (moved, dropped) = merge_offers(&[], &duplicate);
+        assert_eq!(moved.len(), 1);
+        assert!(dropped.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::{
+    compare::{numeric_value, round},
+    models::{ComparisonRow, ComparisonValue, Product},
+    schemas::{EnergyProfile, Usage},
+    stats::field_value,
+};
+
+pub const DEFAULT_YEARS: u32 = 10;
+pub const MAX_YEARS: u32 = 50;
+
+/// Tariff and usage to estimate running costs with. Usage not given falls
+/// back to the category's `default_usage`.
+#[derive(Debug, Clone, Copy)]
+pub struct EnergyTerms {
+    pub tariff: f64, // price per kWh
+    pub hours_per_day: Option<f64>,
+    pub cycles_per_week: Option<f64>,
+    pub years: u32,
+}
+
+impl EnergyTerms {
+    pub fn new(
+        tariff: f64,
+        hours_per_day: Option<f64>,
+        cycles_per_week: Option<f64>,
+        years: Option<u32>,
+    ) -> Result<Self, String> {
+        if !tariff.is_finite() || tariff < 0.0 {
+            return Err("tariff must be a non-negative number".to_string());
+        }
+        if hours_per_day.is_some_and(|h| !(0.0..=24.0).contains(&h)) {
+            return Err("hours_per_day must be between 0 and 24".to_string());
+        }
+        if cycles_per_week.is_some_and(|c| !c.is_finite() || c < 0.0) {
+            return Err("cycles_per_week must be a non-negative number".to_string());
+        }
+
+        let years = years.unwrap_or(DEFAULT_YEARS);
+        if !(1..=MAX_YEARS).contains(&years) {
+            return Err(format!("years must be between 1 and {}", MAX_YEARS));
+        }
+
+        Ok(EnergyTerms {
+            tariff,
+            hours_per_day,
+            cycles_per_week,
+            years,
+        })
+    }
+
+    /// Hours per day or cycles per week for the profile's kind of usage.
+    pub fn usage(&self, profile: &EnergyProfile) -> f64 {
+        match profile.usage {
+            Usage::HoursPerDay => self.hours_per_day,
+            Usage::CyclesPerWeek => self.cycles_per_week,
+        }
+        .unwrap_or(profile.default_usage)
+    }
+}
+
+/// Estimated running cost of one product.
+#[derive(Debug, Clone, Copy)]
+pub struct EnergyEstimate {
+    pub power: f64, // the product's value of the profile's field
+    pub annual_kwh: f64,
+    pub annual_cost: f64,
+    pub lifetime_cost: f64,
+    pub total_cost_of_ownership: Option<f64>, // euro price plus lifetime cost, when priced
+}
+
+/// `None` when the product has no numeric value for the profile's field.
+pub fn estimate(
+    profile: &EnergyProfile,
+    terms: &EnergyTerms,
+    product: &Product,
+) -> Option<EnergyEstimate> {
+    let power = numeric_value(&field_value(product, &profile.field))?;
+    let usage = terms.usage(profile);
+
+    let annual_kwh = match profile.usage {
+        Usage::HoursPerDay => power / 1000.0 * usage * 365.0,
+        Usage::CyclesPerWeek => power * usage * 365.0 / 7.0,
+    };
+    let annual_cost = annual_kwh * terms.tariff;
+    let lifetime_cost = annual_cost * terms.years as f64;
+    let price = numeric_value(&field_value(product, "price"));
+
+    Some(EnergyEstimate {
+        power,
+        annual_kwh: round(annual_kwh),
+        annual_cost: round(annual_cost),
+        lifetime_cost: round(lifetime_cost),
+        total_cost_of_ownership: price.map(|p| round(p + lifetime_cost)),
+    })
+}
+
+/// Annual energy cost and total cost of ownership rows for a comparison,
+/// using each product's category profile. Products from categories without
+/// one get `null`.
+pub fn cost_rows(
+    profiles: &HashMap<i32, EnergyProfile>,
+    terms: &EnergyTerms,
+    products: &[Product],
+) -> Vec<ComparisonRow> {
+    let estimates: Vec<Option<EnergyEstimate>> = products
+        .iter()
+        .map(|p| {
+            profiles
+                .get(&p.category_id)
+                .and_then(|profile| estimate(profile, terms, p))
+        })
+        .collect();
+
+    let row =
+        |field: &str, label: String, value: fn(&EnergyEstimate) -> Option<f64>| ComparisonRow {
+            field: field.to_string(),
+            label,
+            unit: "EUR".to_string(),
+            higher_is_better: Some(false),
+            values: products
+                .iter()
+                .zip(&estimates)
+                .map(|(p, estimate)| ComparisonValue {
+                    product_id: p.id,
+                    product_name: p.name.clone(),
+                    value: json!(estimate.as_ref().and_then(value)),
+                    delta: None,
+                    delta_percent: None,
+                    verdict: None,
+                    category_percentile: None,
+                })
+                .collect(),
+        };
+
+    vec![
+        row(
+            "annual_energy_cost",
+            "Annual Energy Cost".to_string(),
+            |e| Some(e.annual_cost),
+        ),
+        row(
+            "total_cost_of_ownership",
+            format!("Total Cost of Ownership ({} years)", terms.years),
+            |e| e.total_cost_of_ownership,
+        ),
+    ]
+}
@@ -0,0 +1,65 @@
+use axum::{
+    Json,
+    http::{
+        HeaderMap, StatusCode,
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+    },
+    response::{IntoResponse, Response},
+};
+
+use crate::models::{Product, ProductResponse};
+
+/// Entity tag of a product's current version.
+pub fn product_etag(product: &Product) -> String {
+    format!("\"{}-{}\"", product.id, product.version)
+}
+
+/// Whether `If-None-Match` lists the given tag, meaning the client's copy is current.
+pub fn none_match_satisfied(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| tag_list_contains(v, etag, true))
+}
+
+/// Checks `If-Match` against the given tag. `None` when the header is missing.
+pub fn if_match(headers: &HeaderMap, etag: &str) -> Option<bool> {
+    headers
+        .get(IF_MATCH)
+        .map(|v| v.to_str().is_ok_and(|v| tag_list_contains(v, etag, false)))
+}
+
+/// Response for an `If-Match` that names an outdated version: the current
+/// product and its tag, so the client can merge and retry.
+pub fn precondition_failed(current: Product) -> Response {
+    (
+        StatusCode::PRECONDITION_FAILED,
+        [(ETAG, product_etag(&current))],
+        Json(ProductResponse { product: current }),
+    )
+        .into_response()
+}
+
+/// Responds with the product and its `ETag` header.
+pub fn with_etag(status: StatusCode, product: Product) -> Response {
+    (
+        status,
+        [(ETAG, product_etag(&product))],
+        Json(ProductResponse { product }),
+    )
+        .into_response()
+}
+
+fn tag_list_contains(header: &str, etag: &str, weak: bool) -> bool {
+    header.split(',').map(str::trim).any(|tag| {
+        if tag == "*" {
+            return true;
+        }
+
+        // Weak tags only match under the weak comparison used by If-None-Match
+        match tag.strip_prefix("W/") {
+            Some(tag) => weak && tag == etag,
+            None => tag == etag,
+        }
+    })
+}
@@ -0,0 +1,157 @@
+use std::convert::Infallible;
+
+use axum::{
+    Json,
+    extract::FromRequestParts,
+    http::{HeaderName, HeaderValue, StatusCode, request::Parts},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::auth::{Actor, to_hex};
+
+pub static IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+pub static IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// How long a key and its response are kept.
+const RETENTION_HOURS: i32 = 24;
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Value of the `Idempotency-Key` header, if the client sent one.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey(pub Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IdempotencyKey(
+            parts
+                .headers
+                .get(&IDEMPOTENCY_KEY_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        ))
+    }
+}
+
+/// Outcome of claiming a key for the current request.
+pub enum Claim {
+    /// First use of the key, the request should be processed
+    New,
+    /// The same request was already processed, with this response
+    Replay(Response),
+    /// The key was used for a different request
+    Mismatch,
+}
+
+/// Fingerprint of a request, so a key can't be reused with a different body.
+pub fn fingerprint(endpoint: &str, body: &impl Serialize) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(endpoint.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(serde_json::to_vec(body).unwrap_or_default());
+    to_hex(&hasher.finalize())
+}
+
+/// Claims `key` for the actor inside the request's transaction. A concurrent
+/// request with the same key waits here until the first one commits or rolls
+/// back, and then replays its response.
+pub async fn claim(
+    conn: &mut sqlx::PgConnection,
+    actor: &Actor,
+    key: &str,
+    fingerprint: &str,
+) -> Result<Claim, sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM idempotency_keys WHERE created_at < NOW() - make_interval(hours => $1)",
+    )
+    .bind(RETENTION_HOURS)
+    .execute(&mut *conn)
+    .await?;
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO idempotency_keys (actor_type, actor_id, key, fingerprint)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(actor.kind())
+    .bind(actor.id())
+    .bind(key)
+    .bind(fingerprint)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if inserted == 1 {
+        return Ok(Claim::New);
+    }
+
+    let (stored_fingerprint, status_code, response) =
+        sqlx::query_as::<_, (String, Option<i16>, Option<serde_json::Value>)>(
+            r#"
+            SELECT fingerprint, status_code, response
+            FROM idempotency_keys
+            WHERE actor_type = $1 AND actor_id = $2 AND key = $3
+            "#,
+        )
+        .bind(actor.kind())
+        .bind(actor.id())
+        .bind(key)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    if stored_fingerprint != fingerprint {
+        return Ok(Claim::Mismatch);
+    }
+
+    // Responses are stored in the same transaction that claimed the key, so
+    // a committed row always has one
+    let status = status_code
+        .and_then(|code| StatusCode::from_u16(code as u16).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut replay = (status, Json(response.unwrap_or_default())).into_response();
+    replay.headers_mut().insert(
+        IDEMPOTENT_REPLAYED_HEADER.clone(),
+        HeaderValue::from_static("true"),
+    );
+
+    Ok(Claim::Replay(replay))
+}
+
+/// Stores the response for a claimed key, to be replayed on retries.
+pub async fn store_response<'e, E>(
+    executor: E,
+    actor: &Actor,
+    key: &str,
+    status: StatusCode,
+    body: &impl Serialize,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query(
+        r#"
+        UPDATE idempotency_keys
+        SET status_code = $4, response = $5
+        WHERE actor_type = $1 AND actor_id = $2 AND key = $3
+        "#,
+    )
+    .bind(actor.kind())
+    .bind(actor.id())
+    .bind(key)
+    .bind(status.as_u16() as i16)
+    .bind(serde_json::to_value(body).unwrap_or_default())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Whether a client-supplied key is acceptable.
+pub fn valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LENGTH
+}
@@ -1,9 +1,17 @@
+mod audit;
+mod auth;
+mod cli;
+mod idempotency;
 mod models;
+mod offers;
+mod prices;
+mod revisions;
 mod routes;
+mod slugs;
 
 use axum::{
     Router,
-    http::header::{AUTHORIZATION, CONTENT_TYPE},
+    http::header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH},
     routing::get,
 };
 use sqlx::postgres::PgPoolOptions;
@@ -13,8 +21,18 @@ use tower_http::cors::Any;
 use crate::db::ensure_database_exists;
 
 pub mod compare;
+pub mod computed;
 pub mod db;
+pub mod diff;
+pub mod dimensions;
+pub mod duplicates;
+pub mod energy;
+pub mod etag;
+pub mod pareto;
 pub mod schemas;
+pub mod scoring;
+pub mod similarity;
+pub mod stats;
 
 #[tokio::main]
 pub async fn main() -> color_eyre::Result<()> {
@@ -41,16 +59,64 @@ pub async fn main() -> color_eyre::Result<()> {
     tracing::info!("Syncing category schemas from schemas.yml...");
     schemas::sync_categories(&pool).await?;
 
-    let state = routes::AppState { db: pool };
+    // Administrative subcommands run against the database and exit
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("keys") {
+        return cli::run_keys(&pool, &args[1..]).await;
+    }
+
+    let exchange_rates = match env::var("EXCHANGE_RATES") {
+        Ok(rates) if !rates.is_empty() => offers::ExchangeRates::parse(&rates)
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid EXCHANGE_RATES: {}", e))?,
+        _ => {
+            tracing::warn!(
+                "EXCHANGE_RATES is not set, using {}",
+                offers::DEFAULT_EXCHANGE_RATES
+            );
+            offers::ExchangeRates::parse(offers::DEFAULT_EXCHANGE_RATES)
+                .map_err(|e| color_eyre::eyre::eyre!(e))?
+        }
+    };
+
+    tracing::info!("Syncing prices with exchange rates...");
+    offers::sync_prices(&pool, &exchange_rates).await?;
+
+    let session_secret = match env::var("SESSION_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+        _ => {
+            tracing::warn!("SESSION_SECRET is not set, sessions will not survive a restart");
+            auth::generate_session_secret()
+        }
+    };
+
+    let state = routes::AppState {
+        db: pool,
+        session_secret: session_secret.into(),
+        distributions: Default::default(),
+        exchange_rates: exchange_rates.into(),
+    };
 
     let cors_layer = tower_http::cors::CorsLayer::new()
         .allow_methods(Any)
         .allow_origin(Any)
-        .allow_headers([AUTHORIZATION, CONTENT_TYPE]);
+        .allow_headers([
+            AUTHORIZATION,
+            CONTENT_TYPE,
+            IF_MATCH,
+            IF_NONE_MATCH,
+            audit::REQUEST_ID_HEADER.clone(),
+            idempotency::IDEMPOTENCY_KEY_HEADER.clone(),
+        ])
+        .expose_headers([
+            ETAG,
+            audit::REQUEST_ID_HEADER.clone(),
+            idempotency::IDEMPOTENT_REPLAYED_HEADER.clone(),
+        ]);
 
     let app = Router::new()
         .nest("/api/v1", routes::api_routes())
         .route("/", get(crate::routes::about::get_about))
+        .layer(axum::middleware::from_fn(audit::request_id_middleware))
         .layer(cors_layer)
         .with_state(state);
 
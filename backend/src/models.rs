@@ -1,5 +1,12 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    auth::Role,
+    schemas::{EnergyProfile, SpecificationField, WeightProfile},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Category {
@@ -16,20 +23,159 @@ pub struct Product {
     pub category_id: i32,
     pub category_name: String,
     pub name: String,
+    #[serde(default)]
+    pub slug: String, // follows the name, earlier slugs keep resolving to the product
     pub manufacturer: Option<String>,
     pub model: Option<String>,
     pub specifications: Option<serde_json::Value>,
-    pub price: Option<bigdecimal::BigDecimal>,
+    pub price: Option<bigdecimal::BigDecimal>, // lowest in-stock offer, see `offers::refresh_price`
+    #[serde(default)]
+    pub currency: Option<String>, // of `price`
+    #[serde(default)]
+    pub price_eur: Option<bigdecimal::BigDecimal>, // `price` at the configured exchange rates
+    #[serde(default)]
+    pub offer_count: i32,
+    #[serde(default)]
+    pub version: i32, // incremented on every edit, see `etag::product_etag`
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct User {
+    pub id: i32,
+    pub email: String,
+    pub display_name: String,
+    #[sqlx(try_from = "String")]
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterUser {
+    pub email: String,
+    pub display_name: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub user: User,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignRole {
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserResponse {
+    pub user: User,
+}
+
+/// A product together with its moderation state.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Submission {
+    #[sqlx(flatten)]
+    pub product: Product,
+    pub status: String, // "pending", "approved", "rejected" or "changes_requested"
+    pub submitted_by: Option<i32>,
+    pub moderation_comment: Option<String>,
+    pub moderated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubmissionsResponse {
+    pub submissions: Vec<Submission>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModerationQueueResponse {
+    pub queue: Vec<ModerationQueueItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModerationQueueItem {
+    pub submission: Submission,
+    pub similar: Option<SimilarProduct>, // closest existing product by name
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarProduct {
+    pub product: Product,
+    pub similarity: f64,
+    pub differences: Vec<FieldDifference>, // before = existing product, after = submission
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldDifference {
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModerationAction {
+    Approve,
+    Reject,
+    RequestChanges,
 }
 
 #[derive(Debug, Deserialize)]
+pub struct ModerationDecision {
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateProduct {
     pub category_id: i32,
     pub name: String,
     pub manufacturer: Option<String>,
     pub model: Option<String>,
     pub specifications: Option<serde_json::Value>,
-    pub price: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCategory {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCategorySchema {
+    pub specifications: HashMap<String, SpecificationField>,
+    pub profiles: Option<BTreeMap<String, WeightProfile>>, // replaces the weight profiles
+    #[serde(default, deserialize_with = "present")]
+    pub energy: Option<Option<EnergyProfile>>, // replaces the energy profile, `null` removes it
+}
+
+/// Tells a field given as `null` (`Some(None)`) from a missing one (`None`).
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SchemaUpdateQuery {
+    #[serde(default)]
+    pub force: bool, // remove fields even if products still use them
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateProduct {
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub specifications: Option<serde_json::Value>, // merged into the existing specifications
 }
 
 #[derive(Debug, Serialize)]
@@ -48,10 +194,92 @@ pub struct ProductResponse {
 }
 
 #[derive(Debug, Serialize)]
-pub struct ComparisonResponse {
+pub struct CreateProductResponse {
+    pub product: Product,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub possible_duplicates: Vec<DuplicateMatch>, // existing products that look like the same item
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryStatsResponse {
     pub category: Category,
+    pub product_count: usize,
+    pub fields: Vec<FieldStats>, // price first, then the schema's fields
+}
+
+/// Distribution of one field over the approved products of a category.
+#[derive(Debug, Serialize)]
+pub struct FieldStats {
+    pub field: String,
+    pub label: String,
+    pub unit: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub count: usize,   // products with a usable value
+    pub missing: usize, // products without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub numeric: Option<NumericStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boolean: Option<BooleanStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_values: Option<Vec<ValueCount>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NumericStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub percentiles: Percentiles,
+    pub histogram: Vec<HistogramBin>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Percentiles {
+    pub p10: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p90: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBin {
+    pub from: f64,
+    pub to: f64, // inclusive for the last bin only
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BooleanStats {
+    pub true_count: usize,
+    pub false_count: usize,
+    pub true_share: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComparisonResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<Category>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<Category>,
     pub products: Vec<Product>,
     pub comparison_table: Vec<ComparisonRow>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded_fields: Vec<ExcludedField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline_id: Option<i32>, // product the deltas are measured against
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_values: Vec<MissingValues>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub baseline_summary: Vec<BaselineSummary>,
 }
 
 #[derive(Debug, Serialize)]
@@ -59,6 +287,8 @@ pub struct ComparisonRow {
     pub field: String,
     pub label: String,
     pub unit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub higher_is_better: Option<bool>,
     pub values: Vec<ComparisonValue>,
 }
 
@@ -67,18 +297,509 @@ pub struct ComparisonValue {
     pub product_id: i32,
     pub product_name: String,
     pub value: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<f64>, // value minus the baseline's, for numeric rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<Verdict>, // against the baseline, for fields with `higher_is_better`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category_percentile: Option<f64>, // rank within the product's whole category, higher is better
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Better,
+    Worse,
+    Same,
+}
+
+/// How many fields a product wins, loses and ties against the baseline.
+#[derive(Debug, Serialize)]
+pub struct BaselineSummary {
+    pub product_id: i32,
+    pub product_name: String,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_difference: Option<f64>,
+}
+
+/// Fields without a value for one product, reported by the diff view.
+#[derive(Debug, Serialize)]
+pub struct MissingValues {
+    pub product_id: i32,
+    pub product_name: String,
+    pub fields: Vec<String>,
+}
+
+/// A specification field left out of a cross-category comparison.
+#[derive(Debug, Serialize)]
+pub struct ExcludedField {
+    pub field: String,
+    pub label: String,
+    pub categories: Vec<String>, // slugs of the categories that define the field
+    pub reason: String,          // "missing", "type_mismatch" or "unit_mismatch"
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareMode {
+    /// All products must share a category (default)
+    #[default]
+    Category,
+    /// Products may span categories; only shared specification fields are compared
+    Common,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CompareQuery {
+    #[serde(default)]
     pub ids: String, // comma-separated product IDs
+    #[serde(default)]
+    pub slugs: String, // comma-separated product slugs, combined with `ids`
+    #[serde(default)]
+    pub mode: CompareMode,
+    #[serde(default)]
+    pub diff: bool, // only show fields whose values differ
+    pub baseline: Option<i32>, // product id to compare against, defaults to the first product in diff mode
+    pub profile: Option<String>, // weight profile, adds a score row
+    pub units: Option<String>, // "metric" or "imperial", for dimensions fields
+    // Energy terms; with a tariff the table gains running-cost rows
+    pub tariff: Option<f64>,
+    pub hours_per_day: Option<f64>,
+    pub cycles_per_week: Option<f64>,
+    pub years: Option<u32>,
+}
+
+/// How a comparison is rendered, given as query parameters or saved with it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComparisonOptions {
+    #[serde(default)]
+    pub mode: CompareMode,
+    pub profile: Option<String>, // weight profile of the products' categories, adds a score row
+    pub units: Option<String>,   // "metric" or "imperial", for dimensions fields
+    #[serde(default)]
+    pub diff: bool, // only show fields whose values differ
+    pub baseline: Option<i32>,
+    pub tariff: Option<f64>, // euros per kWh, adds energy cost and total cost of ownership rows
+    pub hours_per_day: Option<f64>,
+    pub cycles_per_week: Option<f64>,
+    pub years: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
+pub struct CreateComparison {
+    #[serde(default)]
+    pub ids: Vec<i32>,
+    #[serde(default)]
+    pub slugs: Vec<String>,
+    #[serde(default)]
+    pub options: ComparisonOptions,
+    #[serde(default)]
+    pub freeze: bool, // keep the values as they are now, even if specs change later
+}
+
+#[derive(Debug, FromRow)]
+pub struct SavedComparison {
+    pub id: String,
+    pub product_ids: Vec<i32>,
+    #[sqlx(json)]
+    pub options: ComparisonOptions,
+    pub snapshot: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavedComparisonResponse {
+    pub id: String,
+    pub product_ids: Vec<i32>,
+    pub options: ComparisonOptions,
+    pub frozen: bool,
+    pub created_at: DateTime<Utc>,
+    pub comparison: serde_json::Value, // a `ComparisonResponse`, live or as frozen
+}
+
+#[derive(Debug, Default, Deserialize)]
 pub struct SearchQuery {
     pub q: Option<String>,            // General search query
     pub category: Option<String>,     // Filter by category slug
     pub manufacturer: Option<String>, // Filter by manufacturer
-    pub min_price: Option<f64>,       // Minimum price
-    pub max_price: Option<f64>,       // Maximum price
+    pub min_price: Option<f64>,       // Minimum price in euro cents
+    pub max_price: Option<f64>,       // Maximum price in euro cents
+    pub sort: Option<String>, // "name", "price" or a specification field, "-" prefix for descending
+    // Only products whose dimensions fit within these measurements
+    pub fits_width: Option<f64>,
+    pub fits_height: Option<f64>,
+    pub fits_depth: Option<f64>,
+    pub fits_unit: Option<String>, // "cm" (default), "mm" or "in"
+    pub clearance: Option<f64>,    // free space needed on top of each measurement, in `fits_unit`
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ProductRevision {
+    pub product_id: i32,
+    pub revision: i32,
+    pub action: String, // "create", "update" or "restore"
+    pub actor_type: String,
+    pub actor_id: Option<i32>,
+    pub actor_name: String,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(json)]
+    pub snapshot: Product,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionsResponse {
+    pub revisions: Vec<ProductRevision>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffQuery {
+    pub from: i32,
+    pub to: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionDiffResponse {
+    pub product_id: i32,
+    pub from: i32,
+    pub to: i32,
+    pub differences: Vec<FieldDifference>, // before = `from`, after = `to`
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub actor_type: String,
+    pub actor_id: Option<i32>,
+    pub actor_name: String,
+    pub request_id: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub changes: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditLogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub entity_type: Option<String>,  // "product", "category" or "user"
+    pub entity_id: Option<String>,    // product id, category slug or user id
+    pub actor_type: Option<String>,   // "user", "api_key" or "system"
+    pub actor_id: Option<i32>,        // user or API key id
+    pub action: Option<String>,       // "create", "update", "archive" or "sync"
+    pub request_id: Option<String>,   // all changes made by one request
+    pub since: Option<DateTime<Utc>>, // inclusive
+    pub until: Option<DateTime<Utc>>, // exclusive
+    pub before_id: Option<i64>,       // for paging backwards through older entries
+    pub limit: Option<i64>,           // default 100, at most 1000
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    ManufacturerModel,
+    Name,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateMatch {
+    pub product: Product,
+    pub similarity: f64,
+    pub reason: DuplicateReason,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicatePair {
+    pub product: Product,
+    pub duplicate: Product,
+    pub similarity: f64,
+    pub reason: DuplicateReason,
+    pub differences: Vec<FieldDifference>, // before = product, after = duplicate
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicatesResponse {
+    pub duplicates: Vec<DuplicatePair>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicatesQuery {
+    pub category: Option<String>, // category slug
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeProducts {
+    pub duplicate_id: i32, // merged into the product in the path, which survives
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimilarQuery {
+    pub limit: Option<usize>, // default 5, at most 50
+    #[serde(default)]
+    pub include_price: bool, // count price as one of the dimensions
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarProductsResponse {
+    pub product: Product,
+    pub similar: Vec<NearbyProduct>, // closest first
+}
+
+#[derive(Debug, Serialize)]
+pub struct NearbyProduct {
+    pub product: Product,
+    pub distance: f64, // 0 for identical specifications, at most 1
+    pub most_different: Vec<FieldDistance>,
+}
+
+/// How far apart two products are on one field, normalized to 0-1 over the category.
+#[derive(Debug, Serialize)]
+pub struct FieldDistance {
+    pub field: String,
+    pub label: String,
+    pub distance: f64,
+    pub value: serde_json::Value,       // the product's value
+    pub other_value: serde_json::Value, // the similar product's value
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParetoQuery {
+    pub x: Option<String>, // "price" (default), "score" or "spec.<field>"
+    pub y: Option<String>, // "score" (default), "price" or "spec.<field>"
+    // Search filters, as in `SearchQuery`
+    pub q: Option<String>,
+    pub manufacturer: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParetoResponse {
+    pub category: Category,
+    pub x: ParetoAxis,
+    pub y: ParetoAxis,
+    pub frontier: Vec<ParetoPoint>,
+    pub dominated: Vec<ParetoPoint>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<i32>, // products without a value on one of the axes
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParetoAxis {
+    pub name: String,
+    pub higher_is_better: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParetoPoint {
+    pub product: Product,
+    pub x: f64,
+    pub y: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dominated_by: Vec<i32>, // ids of the products that beat this one on both axes
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BestQuery {
+    pub profile: Option<String>, // one of the category's weight profiles
+    pub weights: Option<String>, // explicit weights, "field:weight,field:weight"
+    pub limit: Option<usize>,    // default 10, at most 100
+    // Search filters, as in `SearchQuery`
+    pub q: Option<String>,
+    pub manufacturer: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BestResponse {
+    pub category: Category,
+    pub profile: Option<String>,
+    pub weights: WeightProfile,
+    pub profiles: Vec<String>, // profiles defined for the category
+    pub matched: usize,        // products matching the filters
+    pub products: Vec<RankedProduct>,
+    pub best_value: Vec<ValueProduct>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unscored: Vec<i32>, // matching products without a value on any weighted field
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedProduct {
+    pub rank: usize,
+    pub product: Product,
+    pub score: f64, // 0-100, weighted mean of category percentiles
+    pub breakdown: Vec<ScoreContribution>, // largest contribution first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>, // weighted fields without a value, counted as 0
+    pub coverage: f64, // share of the weights the product has values for, 0-1
+}
+
+/// One field's part in a weighted score. `contribution` is `weight * percentile`,
+/// and the contributions of a product add up to its score.
+#[derive(Debug, Serialize)]
+pub struct ScoreContribution {
+    pub field: String,
+    pub label: String,
+    pub weight: f64, // share of all weights, including fields the product has no value for
+    pub percentile: f64,
+    pub contribution: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValueProduct {
+    pub rank: usize,
+    pub product: Product,
+    pub score: f64,
+    pub value: f64, // score points per 100 euros of price
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnergyQuery {
+    pub tariff: f64,                  // euros per kWh
+    pub hours_per_day: Option<f64>,   // defaults to the category's usage profile
+    pub cycles_per_week: Option<f64>, // defaults to the category's usage profile
+    pub years: Option<u32>,           // lifetime, default 10
+    // Search filters, as in `SearchQuery`
+    pub q: Option<String>,
+    pub manufacturer: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnergyResponse {
+    pub category: Category,
+    pub energy: EnergyProfile,
+    pub usage: f64, // hours per day or cycles per week used for the estimates
+    pub tariff: f64,
+    pub years: u32,
+    pub products: Vec<EnergyCost>, // lowest total cost of ownership first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<i32>, // products without a value for the power field
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnergyCost {
+    pub product: Product,
+    pub power: f64,
+    pub annual_kwh: f64,
+    pub annual_cost: f64,
+    pub lifetime_cost: f64,
+    pub total_cost_of_ownership: Option<f64>, // euro price plus lifetime cost, null without a price
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceHistoryQuery {
+    pub since: Option<DateTime<Utc>>, // inclusive
+    pub until: Option<DateTime<Utc>>, // exclusive
+    #[serde(default)]
+    pub interval: PriceInterval,
+}
+
+/// Bucket size for downsampling a price history. `raw` returns every change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceInterval {
+    #[default]
+    Raw,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriceHistoryResponse {
+    pub product_id: i32,
+    pub current: Option<f64>,
+    pub currency: Option<String>, // of `current`
+    pub current_eur: Option<f64>,
+    pub all_time_low: Option<PricePoint>,  // lowest in euros
+    pub all_time_high: Option<PricePoint>, // highest in euros
+    pub average_30_days_eur: Option<f64>,  // time-weighted
+    pub vs_average_percent: Option<f64>,   // current price against the 30-day average, in euros
+    pub indicator: Option<PriceIndicator>,
+    pub interval: PriceInterval,
+    pub points: Vec<PricePoint>, // oldest first
+}
+
+/// A recorded price, or with an interval the bucket starting at `at`: its
+/// closing price and the lowest and highest price in euros recorded in it.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PricePoint {
+    pub at: DateTime<Utc>,
+    pub price: f64,
+    pub currency: String,
+    pub price_eur: Option<f64>, // at the configured exchange rates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_eur: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high_eur: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>, // "offer", "merge", "rates" or "initial"; "create", "update" or "restore" before offers
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceIndicator {
+    Below,
+    Average,
+    Above,
+}
+
+/// A retailer's price for a product.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Offer {
+    pub id: i32,
+    pub product_id: i32,
+    pub retailer: String,
+    pub price: bigdecimal::BigDecimal,
+    pub currency: String, // ISO 4217 code, one of the configured `offers::ExchangeRates`
+    pub url: Option<String>,
+    pub stock_status: String, // "in_stock", "out_of_stock", "preorder" or "unknown"
+    pub last_seen_at: DateTime<Utc>, // when the offer was last checked at the retailer
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateOffer {
+    pub retailer: String,
+    pub price: f64, // in cents of `currency`
+    pub currency: String,
+    pub url: Option<String>,
+    pub stock_status: Option<String>,        // defaults to "unknown"
+    pub last_seen_at: Option<DateTime<Utc>>, // defaults to now
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOffer {
+    pub retailer: Option<String>,
+    pub price: Option<f64>, // in cents of `currency`
+    pub currency: Option<String>,
+    pub url: Option<String>,
+    pub stock_status: Option<String>,
+    pub last_seen_at: Option<DateTime<Utc>>, // defaults to now, as an update means the offer was seen
+}
+
+#[derive(Debug, Serialize)]
+pub struct OffersResponse {
+    pub product_id: i32,
+    pub lowest: Option<Offer>, // the in-stock offer the product's price comes from
+    pub offers: Vec<Offer>,    // in stock first, cheapest first
+}
+
+#[derive(Debug, Serialize)]
+pub struct OfferResponse {
+    pub offer: Offer,
 }
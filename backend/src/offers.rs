@@ -0,0 +1,273 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use bigdecimal::{BigDecimal, ToPrimitive};
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    models::{Offer, Product},
+    prices::record_price,
+};
+
+/// Rates used when `EXCHANGE_RATES` is not set.
+pub const DEFAULT_EXCHANGE_RATES: &str = "CZK=0.04,EUR=1,GBP=1.17,PLN=0.23,USD=0.92";
+
+pub const STOCK_STATUSES: &[&str] = &["in_stock", "out_of_stock", "preorder", "unknown"];
+
+/// Euros per unit of every currency offers can be priced in, configured as
+/// `EXCHANGE_RATES=CZK=0.04,EUR=1,USD=0.92`. Prices are shown in their own
+/// currency; the rates pick the cheapest offer and give each product a
+/// `price_eur` that filters, rankings and statistics use.
+#[derive(Debug, Clone)]
+pub struct ExchangeRates {
+    rates: BTreeMap<String, f64>,
+}
+
+impl ExchangeRates {
+    pub fn parse(input: &str) -> Result<ExchangeRates, String> {
+        let mut rates = BTreeMap::new();
+
+        for part in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (code, rate) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected CODE=rate, got '{}'", part))?;
+            let code = code.trim();
+            if code.len() != 3 || !code.chars().all(|c| c.is_ascii_uppercase()) {
+                return Err(format!("'{}' is not an ISO 4217 currency code", code));
+            }
+            let rate: f64 = rate
+                .trim()
+                .parse()
+                .ok()
+                .filter(|r: &f64| r.is_finite() && *r > 0.0)
+                .ok_or_else(|| format!("rate of {} must be a positive number", code))?;
+            if rates.insert(code.to_string(), rate).is_some() {
+                return Err(format!("{} is given twice", code));
+            }
+        }
+
+        match rates.get("EUR") {
+            Some(&rate) if rate != 1.0 => Err("the rate of EUR must be 1".to_string()),
+            _ if rates.is_empty() => Err("no currencies given".to_string()),
+            _ => Ok(ExchangeRates { rates }),
+        }
+    }
+
+    pub fn supports(&self, currency: &str) -> bool {
+        self.rates.contains_key(currency)
+    }
+
+    pub fn rate(&self, currency: &str) -> Option<f64> {
+        self.rates.get(currency).copied()
+    }
+
+    /// The offer's price in euros, `None` when its currency has no rate.
+    fn in_euros(&self, offer: &Offer) -> Option<f64> {
+        Some(offer.price.to_f64()? * self.rate(&offer.currency)?)
+    }
+
+    /// Cheapest first. Offers whose currency has no rate, such as one dropped
+    /// from `EXCHANGE_RATES` since, can't be compared and go last.
+    fn cmp_price(&self, a: &Offer, b: &Offer) -> Ordering {
+        match (self.in_euros(a), self.in_euros(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    /// In-stock offers first, then cheapest first.
+    pub fn sort_offers(&self, offers: &mut [Offer]) {
+        offers.sort_by(|a, b| {
+            in_stock(b)
+                .cmp(&in_stock(a))
+                .then_with(|| self.cmp_price(a, b))
+                .then_with(|| a.retailer.cmp(&b.retailer))
+        });
+    }
+
+    /// The cheapest in-stock offer, which sets the product's price.
+    pub fn lowest_offer<'a>(&self, offers: &'a [Offer]) -> Option<&'a Offer> {
+        offers
+            .iter()
+            .filter(|o| in_stock(o))
+            .min_by(|a, b| self.cmp_price(a, b))
+    }
+}
+
+fn in_stock(offer: &Offer) -> bool {
+    offer.stock_status == "in_stock"
+}
+
+pub async fn product_offers(
+    conn: &mut PgConnection,
+    rates: &ExchangeRates,
+    product_id: i32,
+) -> Result<Vec<Offer>, sqlx::Error> {
+    let mut offers = sqlx::query_as::<_, Offer>(
+        r#"
+        SELECT id, product_id, retailer, price, currency, url, stock_status, last_seen_at
+        FROM offers
+        WHERE product_id = $1
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(conn)
+    .await?;
+
+    rates.sort_offers(&mut offers);
+    Ok(offers)
+}
+
+/// Sets the product's price and currency to its lowest in-stock offer, or
+/// none when nothing is in stock, along with the price in euros and its offer
+/// count. Call it inside the transaction that changed the offers. Returns the
+/// product when any of them changed, with its version bumped so cached copies
+/// are revalidated.
+pub async fn refresh_price(
+    conn: &mut PgConnection,
+    rates: &ExchangeRates,
+    product_id: i32,
+) -> Result<Option<Product>, sqlx::Error> {
+    let offers = product_offers(&mut *conn, rates, product_id).await?;
+    let lowest = rates.lowest_offer(&offers);
+    let price: Option<&BigDecimal> = lowest.map(|o| &o.price);
+    let currency = lowest.map(|o| o.currency.as_str());
+    let rate = currency.and_then(|c| rates.rate(c));
+
+    sqlx::query_as::<_, Product>(
+        r#"
+        UPDATE products p
+        SET price = $2,
+            currency = $3,
+            price_eur = ROUND($2::numeric * $5::float8::numeric, 2),
+            offer_count = $4,
+            version = p.version + 1,
+            updated_at = NOW()
+        FROM categories c
+        WHERE p.id = $1 AND c.id = p.category_id
+            AND (p.price IS DISTINCT FROM $2::numeric
+                OR p.currency IS DISTINCT FROM $3::varchar
+                OR p.price_eur IS DISTINCT FROM ROUND($2::numeric * $5::float8::numeric, 2)
+                OR p.offer_count <> $4)
+        RETURNING p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        "#,
+    )
+    .bind(product_id)
+    .bind(price)
+    .bind(currency)
+    .bind(offers.len() as i32)
+    .bind(rate)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Brings prices in line with the configured rates at startup: a rate change
+/// can change a product's cheapest offer and its price in euros. Price history
+/// is converted at the same rates, so a rate change alone doesn't move a
+/// price against its average.
+pub async fn sync_prices(pool: &PgPool, rates: &ExchangeRates) -> Result<(), sqlx::Error> {
+    let product_ids: Vec<i32> = sqlx::query_scalar(
+        r#"
+        SELECT DISTINCT p.id
+        FROM products p
+        JOIN offers o ON o.product_id = p.id
+        WHERE p.status NOT IN ('rejected', 'merged')
+        ORDER BY p.id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut updated = 0;
+    for product_id in product_ids {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT id FROM products WHERE id = $1 FOR UPDATE")
+            .bind(product_id)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(product) = refresh_price(&mut tx, rates, product_id).await? {
+            record_price(&mut *tx, &product, "rates").await?;
+            updated += 1;
+        }
+        tx.commit().await?;
+    }
+
+    let (currencies, rates): (Vec<&str>, Vec<f64>) = rates
+        .rates
+        .iter()
+        .map(|(code, rate)| (code.as_str(), *rate))
+        .unzip();
+    let converted = sqlx::query(
+        r#"
+        UPDATE price_history h
+        SET price_eur = ROUND(h.price * r.rate::numeric, 2)
+        FROM UNNEST($1::varchar[], $2::float8[]) AS r(currency, rate)
+        WHERE h.currency = r.currency
+            AND h.price_eur IS DISTINCT FROM ROUND(h.price * r.rate::numeric, 2)
+        "#,
+    )
+    .bind(&currencies)
+    .bind(&rates)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    tracing::info!(
+        "Synced prices with exchange rates: {} products updated, {} history entries converted",
+        updated,
+        converted
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer(retailer: &str, price: i32, currency: &str, stock_status: &str) -> Offer {
+        Offer {
+            id: 0,
+            product_id: 1,
+            retailer: retailer.to_string(),
+            price: price.into(),
+            currency: currency.to_string(),
+            url: None,
+            stock_status: stock_status.to_string(),
+            last_seen_at: chrono::Utc::now(),
+        }
+    }
+
+    fn retailers(offers: &[Offer]) -> Vec<&str> {
+        offers.iter().map(|o| o.retailer.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_in_stock_then_cheapest_in_euros() {
+        let rates = ExchangeRates::parse("CZK=0.04,EUR=1").unwrap();
+        let mut offers = vec![
+            offer("a", 500, "EUR", "in_stock"),
+            offer("b", 10000, "CZK", "in_stock"),
+            offer("c", 100, "EUR", "out_of_stock"),
+        ];
+
+        rates.sort_offers(&mut offers);
+        assert_eq!(retailers(&offers), ["b", "a", "c"]);
+        assert_eq!(rates.lowest_offer(&offers).unwrap().retailer, "b");
+    }
+
+    #[test]
+    fn offers_without_a_rate_go_last() {
+        let rates = ExchangeRates::parse("EUR=1").unwrap();
+        let mut offers = vec![
+            offer("a", 0, "USD", "in_stock"),
+            offer("b", 900, "EUR", "in_stock"),
+            offer("c", 0, "EUR", "in_stock"),
+        ];
+
+        rates.sort_offers(&mut offers);
+        assert_eq!(retailers(&offers), ["c", "b", "a"]);
+        assert_eq!(rates.lowest_offer(&offers).unwrap().retailer, "c");
+    }
+}
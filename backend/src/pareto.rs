@@ -0,0 +1,106 @@
+use crate::{
+    models::{Category, ParetoPoint},
+    stats::has_numeric_type,
+};
+
+/// One axis of a Pareto chart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Axis {
+    Price,
+    Score,
+    Spec(String),
+}
+
+impl Axis {
+    /// Parses `price`, `score` or `spec.<field>`, where the field must be a
+    /// numeric field of the category with `higher_is_better` set.
+    pub fn parse(category: &Category, axis: &str) -> Result<Axis, String> {
+        match axis {
+            "price" => Ok(Axis::Price),
+            "score" => Ok(Axis::Score),
+            _ => {
+                let field = axis
+                    .strip_prefix("spec.")
+                    .ok_or_else(|| format!("unknown axis '{}'", axis))?;
+                let definition = category
+                    .specification_schema
+                    .as_ref()
+                    .and_then(|s| s.get(field))
+                    .ok_or_else(|| format!("unknown field '{}'", field))?;
+
+                if !has_numeric_type(definition) {
+                    return Err(format!("field '{}' is not a number", field));
+                }
+                if !definition
+                    .get("higher_is_better")
+                    .is_some_and(|v| v.is_boolean())
+                {
+                    return Err(format!("field '{}' has no higher_is_better", field));
+                }
+
+                Ok(Axis::Spec(field.to_string()))
+            }
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Axis::Price => "price".to_string(),
+            Axis::Score => "score".to_string(),
+            Axis::Spec(field) => format!("spec.{}", field),
+        }
+    }
+
+    pub fn higher_is_better(&self, category: &Category) -> bool {
+        match self {
+            Axis::Price => false,
+            Axis::Score => true,
+            Axis::Spec(field) => category
+                .specification_schema
+                .as_ref()
+                .and_then(|s| s.get(field))
+                .and_then(|d| d.get("higher_is_better"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+        }
+    }
+}
+
+/// Splits points into the Pareto frontier and the dominated rest. A point is
+/// dominated when another one is at least as good on both axes and better on
+/// one; dominated points list every point that beats them. The frontier is
+/// ordered along the x axis.
+pub fn pareto_frontier(
+    mut points: Vec<ParetoPoint>,
+    x_higher_is_better: bool,
+    y_higher_is_better: bool,
+) -> (Vec<ParetoPoint>, Vec<ParetoPoint>) {
+    let oriented = |p: &ParetoPoint| {
+        (
+            if x_higher_is_better { p.x } else { -p.x },
+            if y_higher_is_better { p.y } else { -p.y },
+        )
+    };
+
+    let dominated_by: Vec<Vec<i32>> = points
+        .iter()
+        .map(|point| {
+            let (x, y) = oriented(point);
+            points
+                .iter()
+                .filter(|other| {
+                    let (ox, oy) = oriented(other);
+                    ox >= x && oy >= y && (ox > x || oy > y)
+                })
+                .map(|other| other.product.id)
+                .collect()
+        })
+        .collect();
+
+    for (point, beaten_by) in points.iter_mut().zip(dominated_by) {
+        point.dominated_by = beaten_by;
+    }
+
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.product.id.cmp(&b.product.id)));
+    points.into_iter().partition(|p| p.dominated_by.is_empty())
+}
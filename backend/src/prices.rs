@@ -0,0 +1,101 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::{PriceIndicator, Product};
+
+/// Days the current price is compared against.
+pub const AVERAGE_DAYS: i64 = 30;
+
+/// Prices within this percentage of the average count as average.
+const AVERAGE_BAND_PERCENT: f64 = 1.0;
+
+/// Adds the product's price, in its currency and in euros, to its history when
+/// it differs from the last recorded one. Call it inside the transaction that
+/// changed the product.
+pub async fn record_price<'e, E>(
+    executor: E,
+    product: &Product,
+    source: &str,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let (Some(price), Some(currency)) = (&product.price, &product.currency) else {
+        return Ok(());
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO price_history (product_id, price, currency, price_eur, source)
+        SELECT $1, $2, $3, $4, $5
+        WHERE NOT EXISTS (
+            SELECT 1
+            FROM (
+                SELECT price, currency FROM price_history
+                WHERE product_id = $1
+                ORDER BY recorded_at DESC, id DESC
+                LIMIT 1
+            ) last
+            WHERE last.price = $2 AND last.currency = $3
+        )
+        "#,
+    )
+    .bind(product.id)
+    .bind(price)
+    .bind(currency)
+    .bind(&product.price_eur)
+    .bind(source)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Time-weighted average of a price history over the `days` before `now`.
+/// Each price counts for as long as it was current; the window starts at the
+/// first recorded price if that is more recent. `history` is oldest first.
+pub fn time_weighted_average(
+    history: &[(DateTime<Utc>, f64)],
+    now: DateTime<Utc>,
+    days: i64,
+) -> Option<f64> {
+    let window_start = now - Duration::days(days);
+
+    let mut total = 0.0;
+    let mut seconds = 0.0;
+    for (i, (at, price)) in history.iter().enumerate() {
+        let start = (*at).max(window_start);
+        let end = history.get(i + 1).map_or(now, |(next, _)| (*next).min(now));
+        if end <= start {
+            continue;
+        }
+
+        let duration = (end - start).num_milliseconds() as f64 / 1000.0;
+        total += price * duration;
+        seconds += duration;
+    }
+
+    if seconds > 0.0 {
+        Some(total / seconds)
+    } else {
+        history.last().map(|(_, price)| *price)
+    }
+}
+
+/// How the current price compares to the average, as a percentage and a
+/// below/average/above verdict.
+pub fn compare_to_average(current: f64, average: f64) -> Option<(f64, PriceIndicator)> {
+    if average <= 0.0 {
+        return None;
+    }
+
+    let percent = (current - average) / average * 100.0;
+    let indicator = if percent <= -AVERAGE_BAND_PERCENT {
+        PriceIndicator::Below
+    } else if percent >= AVERAGE_BAND_PERCENT {
+        PriceIndicator::Above
+    } else {
+        PriceIndicator::Average
+    };
+
+    Some((percent, indicator))
+}
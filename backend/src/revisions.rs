@@ -0,0 +1,33 @@
+use crate::{auth::Actor, models::Product};
+
+/// Stores a snapshot of `product` as its next revision. Call it inside the
+/// transaction that changed the product, after taking a lock on its row.
+pub async fn record_revision<'e, E>(
+    executor: E,
+    product: &Product,
+    actor: &Actor,
+    action: &str,
+) -> Result<i32, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let snapshot = serde_json::to_value(product).unwrap_or_default();
+
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO product_revisions (product_id, revision, action, actor_type, actor_id, actor_name, snapshot)
+        SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, $3, $4, $5, $6
+        FROM product_revisions
+        WHERE product_id = $1
+        RETURNING revision
+        "#,
+    )
+    .bind(product.id)
+    .bind(action)
+    .bind(actor.kind())
+    .bind(actor.id())
+    .bind(actor.name())
+    .bind(snapshot)
+    .fetch_one(executor)
+    .await
+}
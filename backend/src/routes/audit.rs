@@ -0,0 +1,94 @@
+use axum::extract::State;
+
+use crate::{
+    auth::EditorAuth,
+    models::{AuditLogEntry, AuditLogResponse, AuditQuery},
+    routes::AppState,
+};
+
+use axum::{Json, extract::Query, http::StatusCode};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+pub async fn list_audit_entries(
+    EditorAuth(_actor): EditorAuth,
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditLogResponse>, StatusCode> {
+    let mut sql = String::from(
+        r#"
+        SELECT id, occurred_at, actor_type, actor_id, actor_name, request_id, action, entity_type, entity_id, before, after, changes
+        FROM audit_log
+        WHERE 1=1
+        "#,
+    );
+
+    // Track parameter count
+    let mut param_count = 0;
+
+    let text_filters = [
+        ("entity_type", &query.entity_type),
+        ("entity_id", &query.entity_id),
+        ("actor_type", &query.actor_type),
+        ("action", &query.action),
+        ("request_id", &query.request_id),
+    ];
+
+    for (column, value) in &text_filters {
+        if value.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND {} = ${}", column, param_count));
+        }
+    }
+    if query.actor_id.is_some() {
+        param_count += 1;
+        sql.push_str(&format!(" AND actor_id = ${}", param_count));
+    }
+    if query.since.is_some() {
+        param_count += 1;
+        sql.push_str(&format!(" AND occurred_at >= ${}", param_count));
+    }
+    if query.until.is_some() {
+        param_count += 1;
+        sql.push_str(&format!(" AND occurred_at < ${}", param_count));
+    }
+    if query.before_id.is_some() {
+        param_count += 1;
+        sql.push_str(&format!(" AND id < ${}", param_count));
+    }
+
+    param_count += 1;
+    sql.push_str(&format!(" ORDER BY id DESC LIMIT ${}", param_count));
+
+    // Bind in the same order the conditions were added
+    let mut db_query = sqlx::query_as::<_, AuditLogEntry>(&sql);
+
+    for (_, value) in &text_filters {
+        if let Some(value) = value {
+            db_query = db_query.bind(value);
+        }
+    }
+    if let Some(actor_id) = query.actor_id {
+        db_query = db_query.bind(actor_id);
+    }
+    if let Some(since) = query.since {
+        db_query = db_query.bind(since);
+    }
+    if let Some(until) = query.until {
+        db_query = db_query.bind(until);
+    }
+    if let Some(before_id) = query.before_id {
+        db_query = db_query.bind(before_id);
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    db_query = db_query.bind(limit);
+
+    let entries = db_query.fetch_all(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to fetch audit log: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(AuditLogResponse { entries }))
+}
@@ -1,17 +1,46 @@
 use axum::extract::State;
+use std::collections::BTreeMap;
+
+use sqlx::{Postgres, Transaction};
 
 use crate::{
-    models::{CategoriesResponse, Category, Product, ProductsResponse},
-    routes::AppState,
+    audit::{AuditEntry, RequestId},
+    auth::AdminAuth,
+    compare::{numeric_value, round},
+    computed::apply_computed,
+    energy::{EnergyTerms, estimate},
+    models::{
+        BestQuery, BestResponse, CategoriesResponse, Category, CategoryStatsResponse, EnergyCost,
+        EnergyQuery, EnergyResponse, ParetoAxis, ParetoPoint, ParetoQuery, ParetoResponse, Product,
+        ProductsResponse, RankedProduct, SchemaUpdateQuery, SearchQuery, UpdateCategory,
+        UpdateCategorySchema, ValueProduct,
+    },
+    pareto::{Axis, pareto_frontier},
+    routes::{
+        AppState, audit_error, begin, commit,
+        products::{
+            category_distribution, energy_profiles, find_products, weight_profiles, with_computed,
+        },
+    },
+    schemas::{
+        CategoryDefinition, EnergyProfile, WeightProfile, validate_energy, validate_profiles,
+        validate_specifications,
+    },
+    scoring::{default_weights, parse_weights, score, weighted_score},
+    stats::{category_stats, field_value},
 };
 
-use axum::{Json, extract::Path, http::StatusCode};
+use axum::{
+    Json,
+    extract::{Path, Query},
+    http::StatusCode,
+};
 
 pub async fn list_categories(
     State(state): State<AppState>,
 ) -> Result<Json<CategoriesResponse>, StatusCode> {
     let categories = sqlx::query_as::<_, Category>(
-        "SELECT id, name, slug, description, specification_schema FROM categories ORDER BY name",
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE archived_at IS NULL ORDER BY name",
     )
     .fetch_all(&state.db)
     .await
@@ -28,7 +57,7 @@ pub async fn get_category(
     Path(slug): Path<String>,
 ) -> Result<Json<Category>, StatusCode> {
     let category = sqlx::query_as::<_, Category>(
-        "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1",
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1 AND archived_at IS NULL",
     )
     .bind(&slug)
     .fetch_one(&state.db)
@@ -45,12 +74,12 @@ pub async fn list_products_by_category(
     State(state): State<AppState>,
     Path(slug): Path<String>,
 ) -> Result<Json<ProductsResponse>, StatusCode> {
-    let products = sqlx::query_as::<_, Product>(
+    let mut products = sqlx::query_as::<_, Product>(
         r#"
-        SELECT p.id, p.category_id, c.name as category_name, p.name, p.manufacturer, p.model, p.specifications, p.price
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
         FROM products p
         JOIN categories c ON p.category_id = c.id
-        WHERE c.slug = $1
+        WHERE c.slug = $1 AND c.archived_at IS NULL AND p.status = 'approved'
         ORDER BY p.name
         "#,
     )
@@ -61,6 +90,701 @@ pub async fn list_products_by_category(
         tracing::error!("Failed to fetch products for category {}: {}", slug, e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    with_computed(&state.db, &mut products).await?;
 
     Ok(Json(ProductsResponse { products }))
 }
+
+pub async fn get_category_stats(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<CategoryStatsResponse>, StatusCode> {
+    let category = sqlx::query_as::<_, Category>(
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1 AND archived_at IS NULL",
+    )
+    .bind(&slug)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut products = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.category_id = $1 AND p.status = 'approved'
+        "#,
+    )
+    .bind(category.id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch products for category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    apply_computed(&category, &mut products);
+
+    let fields = category_stats(&category, &products);
+
+    Ok(Json(CategoryStatsResponse {
+        category,
+        product_count: products.len(),
+        fields,
+    }))
+}
+
+pub async fn get_category_pareto(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<ParetoQuery>,
+) -> Result<Json<ParetoResponse>, StatusCode> {
+    let category = sqlx::query_as::<_, Category>(
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1 AND archived_at IS NULL",
+    )
+    .bind(&slug)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let parse_axis = |axis: &str| {
+        Axis::parse(&category, axis).map_err(|e| {
+            tracing::warn!("Rejected Pareto axis for category {}: {}", slug, e);
+            StatusCode::BAD_REQUEST
+        })
+    };
+    let x = parse_axis(query.x.as_deref().unwrap_or("price"))?;
+    let y = parse_axis(query.y.as_deref().unwrap_or("score"))?;
+
+    let products = find_products(
+        &state,
+        &SearchQuery {
+            q: query.q,
+            category: Some(slug.clone()),
+            manufacturer: query.manufacturer,
+            min_price: query.min_price,
+            max_price: query.max_price,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let distribution = category_distribution(&state, &category).await?;
+    let axis_value = |axis: &Axis, product: &Product| match axis {
+        Axis::Price => numeric_value(&field_value(product, "price")),
+        Axis::Score => score(&category, &distribution, product),
+        Axis::Spec(field) => numeric_value(&field_value(product, field)),
+    };
+
+    let mut points = Vec::new();
+    let mut excluded = Vec::new();
+    for product in products {
+        match (axis_value(&x, &product), axis_value(&y, &product)) {
+            (Some(x), Some(y)) => points.push(ParetoPoint {
+                product,
+                x,
+                y,
+                dominated_by: Vec::new(),
+            }),
+            _ => excluded.push(product.id),
+        }
+    }
+
+    let (x_higher_is_better, y_higher_is_better) =
+        (x.higher_is_better(&category), y.higher_is_better(&category));
+    let (frontier, dominated) = pareto_frontier(points, x_higher_is_better, y_higher_is_better);
+
+    Ok(Json(ParetoResponse {
+        x: ParetoAxis {
+            name: x.name(),
+            higher_is_better: x_higher_is_better,
+        },
+        y: ParetoAxis {
+            name: y.name(),
+            higher_is_better: y_higher_is_better,
+        },
+        category,
+        frontier,
+        dominated,
+        excluded,
+    }))
+}
+
+pub async fn get_category_best(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<BestQuery>,
+) -> Result<Json<BestResponse>, StatusCode> {
+    let category = sqlx::query_as::<_, Category>(
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1 AND archived_at IS NULL",
+    )
+    .bind(&slug)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let profiles = weight_profiles(&state, &[category.id])
+        .await?
+        .remove(&category.id)
+        .unwrap_or_default();
+
+    let weights = match (&query.profile, &query.weights) {
+        (Some(_), Some(_)) => {
+            tracing::warn!(
+                "Rejected best products query for {}: both profile and weights given",
+                slug
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        (Some(profile), None) => profiles.get(profile).cloned().ok_or_else(|| {
+            tracing::warn!("Unknown weight profile '{}' for category {}", profile, slug);
+            StatusCode::BAD_REQUEST
+        })?,
+        (None, Some(weights)) => parse_weights(&category, weights).map_err(|e| {
+            tracing::warn!("Rejected weights for category {}: {}", slug, e);
+            StatusCode::BAD_REQUEST
+        })?,
+        (None, None) => default_weights(&category),
+    };
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+
+    let products = find_products(
+        &state,
+        &SearchQuery {
+            q: query.q,
+            category: Some(slug.clone()),
+            manufacturer: query.manufacturer,
+            min_price: query.min_price,
+            max_price: query.max_price,
+            ..Default::default()
+        },
+    )
+    .await?;
+    let matched = products.len();
+
+    let distribution = category_distribution(&state, &category).await?;
+    let mut scored = Vec::new();
+    let mut unscored = Vec::new();
+    for product in products {
+        match weighted_score(&category, &distribution, &product, &weights) {
+            Some(score) => scored.push((product, score)),
+            None => unscored.push(product.id),
+        }
+    }
+
+    let price = |product: &Product| numeric_value(&field_value(product, "price"));
+    scored.sort_by(|(a, a_score), (b, b_score)| {
+        b_score
+            .score
+            .total_cmp(&a_score.score)
+            // Cheaper first on equal scores, unpriced products last
+            .then_with(|| {
+                let unpriced = |p: &Product| price(p).unwrap_or(f64::INFINITY);
+                unpriced(a).total_cmp(&unpriced(b))
+            })
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    // Score per 100 of price, for the products that have a price
+    let mut value: Vec<(&Product, f64, f64)> = scored
+        .iter()
+        .filter_map(|(product, score)| {
+            let price = price(product).filter(|p| *p > 0.0)?;
+            Some((product, score.score, score.score / price * 100.0))
+        })
+        .collect();
+    value.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.id.cmp(&b.0.id)));
+
+    let best_value = value
+        .into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, (product, score, value))| ValueProduct {
+            rank: i + 1,
+            product: product.clone(),
+            score: round(score),
+            value: round(value),
+        })
+        .collect();
+
+    let ranked = scored
+        .into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, (product, score))| RankedProduct {
+            rank: i + 1,
+            product,
+            score: round(score.score),
+            breakdown: score.contributions,
+            missing: score.missing,
+            coverage: round(score.coverage),
+        })
+        .collect();
+
+    Ok(Json(BestResponse {
+        category,
+        profile: query.profile,
+        weights,
+        profiles: profiles.into_keys().collect(),
+        matched,
+        products: ranked,
+        best_value,
+        unscored,
+    }))
+}
+
+pub async fn get_category_energy(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<EnergyQuery>,
+) -> Result<Json<EnergyResponse>, StatusCode> {
+    let category = sqlx::query_as::<_, Category>(
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1 AND archived_at IS NULL",
+    )
+    .bind(&slug)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let energy = energy_profiles(&state, &[category.id])
+        .await?
+        .remove(&category.id)
+        .ok_or_else(|| {
+            tracing::warn!("Category {} has no energy profile", slug);
+            StatusCode::NOT_FOUND
+        })?;
+
+    let terms = EnergyTerms::new(
+        query.tariff,
+        query.hours_per_day,
+        query.cycles_per_week,
+        query.years,
+    )
+    .map_err(|e| {
+        tracing::warn!("Rejected energy terms for category {}: {}", slug, e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let products = find_products(
+        &state,
+        &SearchQuery {
+            q: query.q,
+            category: Some(slug.clone()),
+            manufacturer: query.manufacturer,
+            min_price: query.min_price,
+            max_price: query.max_price,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let mut costs = Vec::new();
+    let mut excluded = Vec::new();
+    for product in products {
+        match estimate(&energy, &terms, &product) {
+            Some(e) => costs.push(EnergyCost {
+                product,
+                power: e.power,
+                annual_kwh: e.annual_kwh,
+                annual_cost: e.annual_cost,
+                lifetime_cost: e.lifetime_cost,
+                total_cost_of_ownership: e.total_cost_of_ownership,
+            }),
+            None => excluded.push(product.id),
+        }
+    }
+
+    // Unpriced products go last, by running cost alone
+    costs.sort_by(|a, b| {
+        let tco = |c: &EnergyCost| c.total_cost_of_ownership.unwrap_or(f64::INFINITY);
+        tco(a)
+            .total_cmp(&tco(b))
+            .then_with(|| a.lifetime_cost.total_cmp(&b.lifetime_cost))
+            .then_with(|| a.product.id.cmp(&b.product.id))
+    });
+
+    Ok(Json(EnergyResponse {
+        category,
+        usage: terms.usage(&energy),
+        energy,
+        tariff: terms.tariff,
+        years: terms.years,
+        products: costs,
+        excluded,
+    }))
+}
+
+pub async fn create_category(
+    AdminAuth(actor): AdminAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Json(payload): Json<CategoryDefinition>,
+) -> Result<(StatusCode, Json<Category>), StatusCode> {
+    payload.validate().map_err(|e| {
+        tracing::warn!("Rejected category '{}': {}", payload.slug, e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let existing: Option<i32> =
+        sqlx::query_scalar("SELECT id FROM categories WHERE slug = $1 OR LOWER(name) = LOWER($2)")
+            .bind(&payload.slug)
+            .bind(&payload.name)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to check for existing category: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+    if existing.is_some() {
+        tracing::warn!("Category '{}' already exists", payload.slug);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let schema_json = serde_json::to_value(&payload.specifications).map_err(|e| {
+        tracing::error!("Failed to serialize specification schema: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let profiles_json = serde_json::to_value(&payload.profiles).map_err(|e| {
+        tracing::error!("Failed to serialize weight profiles: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let energy_json = payload
+        .energy
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| {
+            tracing::error!("Failed to serialize energy profile: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let mut tx = begin(&state).await?;
+
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        INSERT INTO categories (name, slug, description, specification_schema, weight_profiles, energy_profile, source)
+        VALUES ($1, $2, $3, $4, $5, $6, 'api')
+        RETURNING id, name, slug, description, specification_schema
+        "#,
+    )
+    .bind(&payload.name)
+    .bind(&payload.slug)
+    .bind(&payload.description)
+    .bind(&schema_json)
+    .bind(&profiles_json)
+    .bind(&energy_json)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create category {}: {}", payload.slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    AuditEntry::new(&actor, &request_id, "create", "category", &category.slug)
+        .after(&category)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+
+    tracing::info!(
+        "Created category: {} ({}) by {}",
+        category.name,
+        category.slug,
+        actor
+    );
+
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+pub async fn update_category(
+    AdminAuth(actor): AdminAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(payload): Json<UpdateCategory>,
+) -> Result<Json<Category>, StatusCode> {
+    if payload.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        tracing::warn!("Rejected update of category '{}': empty name", slug);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = begin(&state).await?;
+    let before = fetch_category_for_update(&mut tx, &slug).await?;
+
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        UPDATE categories
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            source = 'api',
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, name, slug, description, specification_schema
+        "#,
+    )
+    .bind(before.id)
+    .bind(&payload.name)
+    .bind(&payload.description)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    AuditEntry::new(&actor, &request_id, "update", "category", &slug)
+        .before(&before)
+        .after(&category)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+
+    tracing::info!("Updated category {} by {}", slug, actor);
+
+    Ok(Json(category))
+}
+
+pub async fn update_category_schema(
+    AdminAuth(actor): AdminAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<SchemaUpdateQuery>,
+    Json(payload): Json<UpdateCategorySchema>,
+) -> Result<Json<Category>, StatusCode> {
+    validate_specifications(&payload.specifications).map_err(|e| {
+        tracing::warn!("Rejected schema for category '{}': {}", slug, e);
+        StatusCode::BAD_REQUEST
+    })?;
+    if let Some(profiles) = &payload.profiles {
+        validate_profiles(&payload.specifications, profiles).map_err(|e| {
+            tracing::warn!("Rejected weight profiles for category '{}': {}", slug, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    }
+    if let Some(Some(energy)) = &payload.energy {
+        validate_energy(&payload.specifications, energy).map_err(|e| {
+            tracing::warn!("Rejected energy profile for category '{}': {}", slug, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    }
+
+    let mut tx = begin(&state).await?;
+    let before = fetch_category_for_update(&mut tx, &slug).await?;
+
+    let removed: Vec<String> = before
+        .specification_schema
+        .as_ref()
+        .and_then(|s| s.as_object())
+        .map(|obj| {
+            obj.keys()
+                .filter(|k| !payload.specifications.contains_key(*k))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if !removed.is_empty() && !query.force {
+        let in_use: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT field
+            FROM unnest($2::text[]) AS field
+            WHERE EXISTS (
+                SELECT 1 FROM products p WHERE p.category_id = $1 AND p.specifications ? field
+            )
+            ORDER BY field
+            "#,
+        )
+        .bind(before.id)
+        .bind(&removed)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check field usage for category {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        if !in_use.is_empty() {
+            tracing::warn!(
+                "Refused to remove fields still used by products in '{}': {}",
+                slug,
+                in_use.join(", ")
+            );
+            return Err(StatusCode::CONFLICT);
+        }
+    }
+
+    // Stored profiles that no longer fit the schema must be replaced with it
+    let (stored_profiles, stored_energy): (serde_json::Value, Option<serde_json::Value>) =
+        sqlx::query_as("SELECT weight_profiles, energy_profile FROM categories WHERE id = $1")
+            .bind(before.id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch profiles of category {}: {}", slug, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    let invalid_profiles = |e: serde_json::Error| {
+        tracing::error!("Invalid stored profiles for category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let orphaned = |e: String| {
+        tracing::warn!(
+            "Refused schema update that breaks the profiles of '{}': {}",
+            slug,
+            e
+        );
+        StatusCode::CONFLICT
+    };
+
+    let profiles = match payload.profiles {
+        Some(profiles) => profiles,
+        None => {
+            let profiles: BTreeMap<String, WeightProfile> =
+                serde_json::from_value(stored_profiles).map_err(invalid_profiles)?;
+            validate_profiles(&payload.specifications, &profiles).map_err(orphaned)?;
+            profiles
+        }
+    };
+    let energy = match payload.energy {
+        Some(energy) => energy,
+        None => {
+            let energy: Option<EnergyProfile> = stored_energy
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(invalid_profiles)?;
+            if let Some(energy) = &energy {
+                validate_energy(&payload.specifications, energy).map_err(orphaned)?;
+            }
+            energy
+        }
+    };
+
+    let schema_json = serde_json::to_value(&payload.specifications).map_err(|e| {
+        tracing::error!("Failed to serialize specification schema: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let profiles_json = serde_json::to_value(&profiles).map_err(|e| {
+        tracing::error!("Failed to serialize weight profiles: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let energy_json = energy
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| {
+            tracing::error!("Failed to serialize energy profile: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        UPDATE categories
+        SET specification_schema = $2, weight_profiles = $3, energy_profile = $4,
+            source = 'api', updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, name, slug, description, specification_schema
+        "#,
+    )
+    .bind(before.id)
+    .bind(&schema_json)
+    .bind(&profiles_json)
+    .bind(&energy_json)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update schema of category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    AuditEntry::new(&actor, &request_id, "update", "category", &slug)
+        .before(&before)
+        .after(&category)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+    state.distributions.invalidate(category.id);
+
+    tracing::info!(
+        "Updated specification schema of category {} by {}",
+        slug,
+        actor
+    );
+
+    Ok(Json(category))
+}
+
+pub async fn archive_category(
+    AdminAuth(actor): AdminAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let mut tx = begin(&state).await?;
+    let before = fetch_category_for_update(&mut tx, &slug).await?;
+
+    sqlx::query(
+        "UPDATE categories SET archived_at = NOW(), source = 'api', updated_at = NOW() WHERE id = $1",
+    )
+    .bind(before.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to archive category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    AuditEntry::new(&actor, &request_id, "archive", "category", &slug)
+        .before(&before)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+
+    tracing::info!("Archived category {} by {}", slug, actor);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Fetches an active category and locks it for the rest of the transaction.
+async fn fetch_category_for_update(
+    tx: &mut Transaction<'static, Postgres>,
+    slug: &str,
+) -> Result<Category, StatusCode> {
+    sqlx::query_as::<_, Category>(
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1 AND archived_at IS NULL FOR UPDATE",
+    )
+    .bind(slug)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch category {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,132 @@
+use axum::extract::State;
+use rand::{Rng, distributions::Alphanumeric};
+use std::collections::HashSet;
+
+use crate::{
+    auth::ContributorAuth,
+    models::{CreateComparison, SavedComparison, SavedComparisonResponse},
+    routes::{
+        AppState,
+        products::{build_comparison, resolve_slugs},
+    },
+};
+
+use axum::{Json, extract::Path, http::StatusCode};
+
+const PUBLIC_ID_LENGTH: usize = 8;
+const MAX_ID_ATTEMPTS: usize = 5;
+
+pub async fn create_comparison(
+    ContributorAuth(actor): ContributorAuth,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateComparison>,
+) -> Result<(StatusCode, Json<SavedComparisonResponse>), StatusCode> {
+    let mut slugs = payload.slugs;
+    slugs.sort();
+    slugs.dedup();
+    let resolved = resolve_slugs(&state, &slugs).await?;
+    if resolved.len() != slugs.len() {
+        tracing::warn!("Comparison refers to unknown product slugs: {:?}", slugs);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let mut product_ids = payload.ids;
+    product_ids.extend(resolved);
+    let mut seen = HashSet::new();
+    product_ids.retain(|id| seen.insert(*id));
+
+    // A saved comparison must show every product it was asked for, so unknown
+    // ids are rejected here; build_comparison rejects mixed categories
+    let comparison = build_comparison(&state, &product_ids, &payload.options).await?;
+    if comparison.products.len() != product_ids.len() {
+        tracing::warn!("Comparison refers to unknown products: {:?}", product_ids);
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let product_ids: Vec<i32> = comparison.products.iter().map(|p| p.id).collect();
+    let comparison = serde_json::to_value(&comparison).unwrap_or_default();
+    let snapshot = payload.freeze.then(|| comparison.clone());
+    let options = serde_json::to_value(&payload.options).unwrap_or_default();
+
+    for _ in 0..MAX_ID_ATTEMPTS {
+        let id: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(PUBLIC_ID_LENGTH)
+            .map(char::from)
+            .collect();
+
+        let saved = sqlx::query_as::<_, SavedComparison>(
+            r#"
+            INSERT INTO saved_comparisons (id, product_ids, options, snapshot, created_by_type, created_by_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (id) DO NOTHING
+            RETURNING id, product_ids, options, snapshot, created_at
+            "#,
+        )
+        .bind(&id)
+        .bind(&product_ids)
+        .bind(&options)
+        .bind(&snapshot)
+        .bind(actor.kind())
+        .bind(actor.id())
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save comparison: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        if let Some(saved) = saved {
+            tracing::info!(
+                "Saved comparison {} of {:?} by {}",
+                saved.id,
+                product_ids,
+                actor
+            );
+            return Ok((StatusCode::CREATED, Json(saved_response(saved, comparison))));
+        }
+    }
+
+    tracing::error!("Failed to find a free comparison id");
+    Err(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn get_comparison(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SavedComparisonResponse>, StatusCode> {
+    let saved = sqlx::query_as::<_, SavedComparison>(
+        "SELECT id, product_ids, options, snapshot, created_at FROM saved_comparisons WHERE id = $1",
+    )
+    .bind(&id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch comparison {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let comparison = match &saved.snapshot {
+        Some(snapshot) => snapshot.clone(),
+        None => {
+            let comparison = build_comparison(&state, &saved.product_ids, &saved.options).await?;
+            serde_json::to_value(&comparison).unwrap_or_default()
+        }
+    };
+
+    Ok(Json(saved_response(saved, comparison)))
+}
+
+fn saved_response(
+    saved: SavedComparison,
+    comparison: serde_json::Value,
+) -> SavedComparisonResponse {
+    SavedComparisonResponse {
+        frozen: saved.snapshot.is_some(),
+        id: saved.id,
+        product_ids: saved.product_ids,
+        options: saved.options,
+        created_at: saved.created_at,
+        comparison,
+    }
+}
@@ -0,0 +1,309 @@
+use axum::extract::State;
+use serde_json::json;
+
+use crate::{
+    audit::{AuditEntry, RequestId},
+    auth::EditorAuth,
+    diff::product_differences,
+    duplicates::{duplicate_match, merge_offers},
+    etag::{if_match, precondition_failed, product_etag, with_etag},
+    models::{DuplicatePair, DuplicatesQuery, DuplicatesResponse, MergeProducts, Product},
+    offers::{product_offers, refresh_price},
+    prices::record_price,
+    revisions::record_revision,
+    routes::{AppState, audit_error, begin, commit, price_history_error, revision_error},
+};
+
+use axum::{
+    Json,
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+
+pub async fn list_duplicates(
+    EditorAuth(_actor): EditorAuth,
+    State(state): State<AppState>,
+    Query(query): Query<DuplicatesQuery>,
+) -> Result<Json<DuplicatesResponse>, StatusCode> {
+    let products = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.status = 'approved' AND ($1::text IS NULL OR c.slug = $1)
+        ORDER BY p.id
+        "#,
+    )
+    .bind(&query.category)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch products for duplicate report: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Products are ordered by id, so the older one of each pair comes first
+    let mut duplicates = Vec::new();
+    for (i, product) in products.iter().enumerate() {
+        for other in &products[i + 1..] {
+            if other.category_id != product.category_id {
+                continue;
+            }
+
+            if let Some((similarity, reason)) = duplicate_match(product, other) {
+                duplicates.push(DuplicatePair {
+                    product: product.clone(),
+                    duplicate: other.clone(),
+                    similarity,
+                    reason,
+                    differences: product_differences(product, other),
+                });
+            }
+        }
+    }
+
+    duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+
+    Ok(Json(DuplicatesResponse { duplicates }))
+}
+
+/// Merges a duplicate into the product in the path. The survivor keeps its own
+/// values and gains the duplicate's missing specifications, manufacturer and
+/// model, and its offers from retailers the survivor has none from. The
+/// duplicate is kept with its revision history and redirects to the survivor.
+pub async fn merge_products(
+    EditorAuth(actor): EditorAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+    Json(payload): Json<MergeProducts>,
+) -> Result<Response, StatusCode> {
+    let duplicate_id = payload.duplicate_id;
+    if duplicate_id == id {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = begin(&state).await?;
+
+    // Lock both rows in id order so concurrent merges can't deadlock
+    let locked = sqlx::query_as::<_, (i32, String)>(
+        r#"
+        SELECT id, status
+        FROM products
+        WHERE id = ANY($1)
+        ORDER BY id
+        FOR UPDATE
+        "#,
+    )
+    .bind(vec![id, duplicate_id])
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to lock products {} and {}: {}", id, duplicate_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let status_of = |product_id: i32| {
+        locked
+            .iter()
+            .find(|(locked_id, _)| *locked_id == product_id)
+            .map(|(_, status)| status.as_str())
+    };
+
+    // Only approved products can absorb a duplicate, which must still be live
+    let duplicate_status = match (status_of(id), status_of(duplicate_id)) {
+        (None, _) | (_, None) => return Err(StatusCode::NOT_FOUND),
+        (Some("approved"), Some("merged" | "rejected")) => return Err(StatusCode::CONFLICT),
+        (Some("approved"), Some(status)) => status.to_string(),
+        (Some(_), Some(_)) => return Err(StatusCode::CONFLICT),
+    };
+
+    let fetch = |product_id: i32| {
+        sqlx::query_as::<_, Product>(
+            r#"
+            SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+            FROM products p
+            JOIN categories c ON p.category_id = c.id
+            WHERE p.id = $1
+            "#,
+        )
+        .bind(product_id)
+    };
+
+    let before = fetch(id).fetch_one(&mut *tx).await.map_err(|e| {
+        tracing::error!("Failed to fetch product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let duplicate = fetch(duplicate_id).fetch_one(&mut *tx).await.map_err(|e| {
+        tracing::error!("Failed to fetch product {}: {}", duplicate_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    match if_match(&headers, &product_etag(&before)) {
+        None => return Err(StatusCode::PRECONDITION_REQUIRED),
+        Some(false) => return Ok(precondition_failed(before)),
+        Some(true) => {}
+    }
+
+    // Specifications are only comparable within a category
+    if before.category_id != duplicate.category_id {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let product = sqlx::query_as::<_, Product>(
+        r#"
+        UPDATE products p
+        SET manufacturer = COALESCE(p.manufacturer, d.manufacturer),
+            model = COALESCE(p.model, d.model),
+            specifications = COALESCE(d.specifications, '{}'::jsonb) || COALESCE(p.specifications, '{}'::jsonb),
+            version = p.version + 1,
+            updated_at = NOW()
+        FROM products d, categories c
+        WHERE p.id = $1 AND d.id = $2 AND c.id = p.category_id
+        RETURNING p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        "#,
+    )
+    .bind(id)
+    .bind(duplicate_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to merge product {} into {}: {}", duplicate_id, id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let offers_error = |e: sqlx::Error| {
+        tracing::error!(
+            "Failed to move offers of product {} to {}: {}",
+            duplicate_id,
+            id,
+            e
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let survivor_offers = product_offers(&mut tx, &state.exchange_rates, id)
+        .await
+        .map_err(offers_error)?;
+    let duplicate_offers = product_offers(&mut tx, &state.exchange_rates, duplicate_id)
+        .await
+        .map_err(offers_error)?;
+    let (moved, dropped) = merge_offers(&survivor_offers, &duplicate_offers);
+
+    sqlx::query("UPDATE offers SET product_id = $1, updated_at = NOW() WHERE id = ANY($2)")
+        .bind(id)
+        .bind(moved.iter().map(|o| o.id).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await
+        .map_err(offers_error)?;
+
+    // The survivor's own offer from the same retailer wins
+    sqlx::query("DELETE FROM offers WHERE id = ANY($1)")
+        .bind(dropped.iter().map(|o| o.id).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await
+        .map_err(offers_error)?;
+    for offer in &dropped {
+        AuditEntry::new(&actor, &request_id, "delete", "offer", offer.id)
+            .before(offer)
+            .record(&mut *tx)
+            .await
+            .map_err(audit_error)?;
+    }
+
+    // The survivor keeps the duplicate's price history
+    sqlx::query("UPDATE price_history SET product_id = $1 WHERE product_id = $2")
+        .bind(id)
+        .bind(duplicate_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to move price history of product {} to {}: {}",
+                duplicate_id,
+                id,
+                e
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let refresh_error = |e: sqlx::Error| {
+        tracing::error!("Failed to refresh prices of merged products: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let product = refresh_price(&mut tx, &state.exchange_rates, id)
+        .await
+        .map_err(refresh_error)?
+        .unwrap_or(product);
+    refresh_price(&mut tx, &state.exchange_rates, duplicate_id)
+        .await
+        .map_err(refresh_error)?;
+
+    let duplicate = sqlx::query_as::<_, Product>(
+        r#"
+        UPDATE products p
+        SET status = 'merged',
+            merged_into = $2,
+            version = p.version + 1,
+            updated_at = NOW()
+        FROM categories c
+        WHERE p.id = $1 AND c.id = p.category_id
+        RETURNING p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        "#,
+    )
+    .bind(duplicate_id)
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to mark product {} as merged: {}", duplicate_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Products merged into the duplicate earlier now redirect straight to the survivor
+    sqlx::query("UPDATE products SET merged_into = $2 WHERE merged_into = $1")
+        .bind(duplicate_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to redirect products merged into {}: {}",
+                duplicate_id,
+                e
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    record_revision(&mut *tx, &product, &actor, "merge")
+        .await
+        .map_err(revision_error)?;
+    record_price(&mut *tx, &product, "merge")
+        .await
+        .map_err(price_history_error)?;
+    record_revision(&mut *tx, &duplicate, &actor, "merged")
+        .await
+        .map_err(revision_error)?;
+
+    AuditEntry::new(&actor, &request_id, "merge", "product", id)
+        .before(&before)
+        .after(&product)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    AuditEntry::new(&actor, &request_id, "merged", "product", duplicate_id)
+        .before(&json!({ "status": duplicate_status, "merged_into": null }))
+        .after(&json!({ "status": "merged", "merged_into": id }))
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+    state.distributions.invalidate(product.category_id);
+
+    tracing::info!("Merged product {} into {} by {}", duplicate_id, id, actor);
+
+    Ok(with_etag(StatusCode::OK, product))
+}
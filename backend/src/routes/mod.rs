@@ -1,29 +1,166 @@
-use axum::{Router, routing::get};
-use sqlx::PgPool;
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, patch, post, put},
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+
+use crate::{offers::ExchangeRates, stats::DistributionCache};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
+    pub session_secret: Arc<[u8]>,
+    pub distributions: Arc<DistributionCache>,
+    pub exchange_rates: Arc<ExchangeRates>,
 }
 
 pub mod about;
+pub mod audit;
 pub mod categories;
+pub mod comparisons;
+pub mod duplicates;
+pub mod moderation;
+pub mod offers;
 pub mod products;
+pub mod revisions;
+pub mod users;
 
 pub fn api_routes() -> Router<AppState> {
     Router::new()
         .route("/about", get(about::get_about))
-        .route("/categories", get(categories::list_categories))
-        .route("/categories/{slug}", get(categories::get_category))
+        .route("/auth/register", post(users::register))
+        .route("/auth/login", post(users::login))
+        .route("/auth/logout", post(users::logout))
+        .route("/auth/me", get(users::get_current_user))
+        .route("/users/{id}/role", put(users::assign_role))
+        .route("/audit", get(audit::list_audit_entries))
+        .route("/moderation/queue", get(moderation::get_queue))
+        .route(
+            "/moderation/{id}/{action}",
+            post(moderation::moderate_submission),
+        )
+        .route("/submissions", get(moderation::list_own_submissions))
+        .route("/submissions/{id}", patch(moderation::revise_submission))
+        .route(
+            "/categories",
+            get(categories::list_categories).post(categories::create_category),
+        )
+        .route(
+            "/categories/{slug}",
+            get(categories::get_category)
+                .put(categories::update_category)
+                .delete(categories::archive_category),
+        )
+        .route(
+            "/categories/{slug}/schema",
+            put(categories::update_category_schema),
+        )
+        .route(
+            "/categories/{slug}/best",
+            get(categories::get_category_best),
+        )
+        .route(
+            "/categories/{slug}/energy",
+            get(categories::get_category_energy),
+        )
+        .route(
+            "/categories/{slug}/pareto",
+            get(categories::get_category_pareto),
+        )
+        .route(
+            "/categories/{slug}/stats",
+            get(categories::get_category_stats),
+        )
         .route(
             "/categories/{slug}/products",
             get(categories::list_products_by_category),
         )
+        .route("/comparisons", post(comparisons::create_comparison))
+        .route("/comparisons/{id}", get(comparisons::get_comparison))
         .route(
             "/products",
             get(products::list_all_products).post(products::create_product),
         )
         .route("/products/search", get(products::search_products))
+        .route("/products/duplicates", get(duplicates::list_duplicates))
+        .route(
+            "/products/by-slug/{slug}",
+            get(products::get_product_by_slug),
+        )
+        .route("/products/{id}/merge", post(duplicates::merge_products))
+        .route("/products/{id}/revisions", get(revisions::list_revisions))
+        .route(
+            "/products/{id}/revisions/diff",
+            get(revisions::diff_revisions),
+        )
+        .route(
+            "/products/{id}/revisions/{rev}",
+            get(revisions::get_revision),
+        )
+        .route(
+            "/products/{id}/revisions/{rev}/restore",
+            post(revisions::restore_revision),
+        )
         .route("/products/compare", get(products::compare_products))
-        .route("/products/{id}", get(products::get_product))
+        .route("/products/{id}/prices", get(products::get_price_history))
+        .route(
+            "/products/{id}/offers",
+            get(offers::list_offers).post(offers::create_offer),
+        )
+        .route(
+            "/products/{id}/similar",
+            get(products::get_similar_products),
+        )
+        .route(
+            "/products/{id}",
+            get(products::get_product).patch(products::update_product),
+        )
+        .route(
+            "/offers/{id}",
+            get(offers::get_offer)
+                .patch(offers::update_offer)
+                .delete(offers::delete_offer),
+        )
+}
+
+/// Starts a transaction for a change that is recorded in the audit log.
+pub(crate) async fn begin(state: &AppState) -> Result<Transaction<'static, Postgres>, StatusCode> {
+    state.db.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+pub(crate) async fn commit(tx: Transaction<'static, Postgres>) -> Result<(), StatusCode> {
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+pub(crate) fn audit_error(e: sqlx::Error) -> StatusCode {
+    tracing::error!("Failed to record audit entry: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+pub(crate) fn revision_error(e: sqlx::Error) -> StatusCode {
+    tracing::error!("Failed to record product revision: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+pub(crate) fn price_history_error(e: sqlx::Error) -> StatusCode {
+    tracing::error!("Failed to record price history: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+pub(crate) fn slug_error(e: sqlx::Error) -> StatusCode {
+    tracing::error!("Failed to update product slug: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+pub(crate) fn idempotency_error(e: sqlx::Error) -> StatusCode {
+    tracing::error!("Failed to process idempotency key: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
 }
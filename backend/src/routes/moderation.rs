@@ -0,0 +1,308 @@
+use axum::extract::State;
+
+use crate::{
+    audit::{AuditEntry, RequestId},
+    auth::{Actor, CurrentUser, ModeratorAuth},
+    diff::product_differences,
+    duplicates::duplicate_match,
+    models::{
+        ModerationAction, ModerationDecision, ModerationQueueItem, ModerationQueueResponse,
+        Product, SimilarProduct, Submission, SubmissionsResponse, UpdateProduct,
+    },
+    revisions::record_revision,
+    routes::{
+        AppState, audit_error, begin, commit, products::prepare_specifications, revision_error,
+        slug_error,
+    },
+    slugs::follow_rename,
+};
+
+use axum::{Json, extract::Path, http::StatusCode};
+
+pub async fn get_queue(
+    ModeratorAuth(_actor): ModeratorAuth,
+    State(state): State<AppState>,
+) -> Result<Json<ModerationQueueResponse>, StatusCode> {
+    let pending = sqlx::query_as::<_, Submission>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version,
+            p.status, p.submitted_by, p.moderation_comment, p.moderated_at
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.status = 'pending'
+        ORDER BY p.created_at, p.id
+        "#,
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch moderation queue: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let category_ids: Vec<i32> = pending.iter().map(|s| s.product.category_id).collect();
+
+    let existing = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.status = 'approved' AND p.category_id = ANY($1)
+        "#,
+    )
+    .bind(&category_ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch products for moderation queue: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let queue = pending
+        .into_iter()
+        .map(|submission| {
+            let similar = existing
+                .iter()
+                .filter(|p| p.category_id == submission.product.category_id)
+                // Same matching as the duplicates report
+                .filter_map(|p| {
+                    duplicate_match(p, &submission.product).map(|(similarity, _)| (p, similarity))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(p, similarity)| SimilarProduct {
+                    product: p.clone(),
+                    similarity,
+                    differences: product_differences(p, &submission.product),
+                });
+
+            ModerationQueueItem {
+                submission,
+                similar,
+            }
+        })
+        .collect();
+
+    Ok(Json(ModerationQueueResponse { queue }))
+}
+
+pub async fn moderate_submission(
+    ModeratorAuth(actor): ModeratorAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path((id, action)): Path<(i32, ModerationAction)>,
+    Json(payload): Json<ModerationDecision>,
+) -> Result<Json<Submission>, StatusCode> {
+    let comment = payload
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+
+    // The submitter needs to know what to change
+    if action == ModerationAction::RequestChanges && comment.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let status = match action {
+        ModerationAction::Approve => "approved",
+        ModerationAction::Reject => "rejected",
+        ModerationAction::RequestChanges => "changes_requested",
+    };
+
+    let mut tx = begin(&state).await?;
+
+    let before = sqlx::query_as::<_, Submission>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version,
+            p.status, p.submitted_by, p.moderation_comment, p.moderated_at
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.id = $1 AND p.status = 'pending'
+        FOR UPDATE OF p
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch submission {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let submission = sqlx::query_as::<_, Submission>(
+        r#"
+        UPDATE products p
+        SET status = $2,
+            moderation_comment = $3,
+            moderated_by = $4,
+            moderated_at = NOW(),
+            version = p.version + 1,
+            updated_at = NOW()
+        FROM categories c
+        WHERE p.id = $1 AND c.id = p.category_id
+        RETURNING p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version,
+            p.status, p.submitted_by, p.moderation_comment, p.moderated_at
+        "#,
+    )
+    .bind(id)
+    .bind(status)
+    .bind(comment)
+    .bind(actor.user_id())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to moderate submission {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    AuditEntry::new(&actor, &request_id, "update", "product", id)
+        .before(&before)
+        .after(&submission)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+    state
+        .distributions
+        .invalidate(submission.product.category_id);
+
+    tracing::info!("Submission {} marked {} by {}", id, status, actor);
+
+    Ok(Json(submission))
+}
+
+pub async fn list_own_submissions(
+    current: CurrentUser,
+    State(state): State<AppState>,
+) -> Result<Json<SubmissionsResponse>, StatusCode> {
+    let submissions = sqlx::query_as::<_, Submission>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version,
+            p.status, p.submitted_by, p.moderation_comment, p.moderated_at
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.submitted_by = $1
+        ORDER BY p.created_at DESC, p.id DESC
+        "#,
+    )
+    .bind(current.user.id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!(
+            "Failed to fetch submissions of user {}: {}",
+            current.user.id,
+            e
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(SubmissionsResponse { submissions }))
+}
+
+/// Lets the submitter revise a submission that is still pending or that a
+/// moderator sent back, returning it to the queue.
+pub async fn revise_submission(
+    current: CurrentUser,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(mut payload): Json<UpdateProduct>,
+) -> Result<Json<Submission>, StatusCode> {
+    if payload.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if payload
+        .specifications
+        .as_ref()
+        .is_some_and(|specs| !specs.is_object())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = begin(&state).await?;
+
+    let before = sqlx::query_as::<_, Submission>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version,
+            p.status, p.submitted_by, p.moderation_comment, p.moderated_at
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.id = $1 AND p.submitted_by = $2
+            AND p.status IN ('pending', 'changes_requested')
+        FOR UPDATE OF p
+        "#,
+    )
+    .bind(id)
+    .bind(current.user.id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch submission {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    prepare_specifications(
+        &mut *tx,
+        before.product.category_id,
+        payload.specifications.as_mut(),
+    )
+    .await?;
+
+    let mut submission = sqlx::query_as::<_, Submission>(
+        r#"
+        UPDATE products p
+        SET name = COALESCE($3, p.name),
+            manufacturer = COALESCE($4, p.manufacturer),
+            model = COALESCE($5, p.model),
+            specifications = CASE
+                WHEN $6::jsonb IS NULL THEN p.specifications
+                ELSE COALESCE(p.specifications, '{}'::jsonb) || $6::jsonb
+            END,
+            status = 'pending',
+            version = p.version + 1,
+            updated_at = NOW()
+        FROM categories c
+        WHERE p.id = $1 AND p.submitted_by = $2 AND c.id = p.category_id
+        RETURNING p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version,
+            p.status, p.submitted_by, p.moderation_comment, p.moderated_at
+        "#,
+    )
+    .bind(id)
+    .bind(current.user.id)
+    .bind(&payload.name)
+    .bind(&payload.manufacturer)
+    .bind(&payload.model)
+    .bind(&payload.specifications)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to revise submission {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    follow_rename(&mut tx, &mut submission.product)
+        .await
+        .map_err(slug_error)?;
+
+    let actor = Actor::User(current.user);
+
+    record_revision(&mut *tx, &submission.product, &actor, "update")
+        .await
+        .map_err(revision_error)?;
+
+    AuditEntry::new(&actor, &request_id, "update", "product", id)
+        .before(&before)
+        .after(&submission)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+
+    Ok(Json(submission))
+}
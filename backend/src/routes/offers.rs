@@ -0,0 +1,414 @@
+use axum::extract::State;
+use bigdecimal::BigDecimal;
+use sqlx::{Postgres, Transaction};
+
+use crate::{
+    audit::{AuditEntry, RequestId},
+    auth::EditorAuth,
+    models::{CreateOffer, Offer, OfferResponse, OffersResponse, Product, UpdateOffer},
+    offers::{ExchangeRates, STOCK_STATUSES, product_offers, refresh_price},
+    prices::record_price,
+    routes::{AppState, audit_error, begin, commit, price_history_error},
+};
+
+use axum::{Json, extract::Path, http::StatusCode};
+
+pub async fn list_offers(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<OffersResponse>, StatusCode> {
+    let mut conn = state.db.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let exists: Option<i32> =
+        sqlx::query_scalar("SELECT id FROM products WHERE id = $1 AND status = 'approved'")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch product {}: {}", id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    if exists.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let rates = &state.exchange_rates;
+    let offers = product_offers(&mut conn, rates, id).await.map_err(|e| {
+        tracing::error!("Failed to fetch offers of product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(OffersResponse {
+        product_id: id,
+        lowest: rates.lowest_offer(&offers).cloned(),
+        offers,
+    }))
+}
+
+pub async fn get_offer(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<OfferResponse>, StatusCode> {
+    let offer = sqlx::query_as::<_, Offer>(
+        r#"
+        SELECT o.id, o.product_id, o.retailer, o.price, o.currency, o.url, o.stock_status, o.last_seen_at
+        FROM offers o
+        JOIN products p ON p.id = o.product_id
+        WHERE o.id = $1 AND p.status = 'approved'
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch offer {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(OfferResponse { offer }))
+}
+
+pub async fn create_offer(
+    EditorAuth(actor): EditorAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(product_id): Path<i32>,
+    Json(payload): Json<CreateOffer>,
+) -> Result<(StatusCode, Json<OfferResponse>), StatusCode> {
+    let checked = validate_offer(
+        &state.exchange_rates,
+        Some(&payload.retailer),
+        Some(payload.price),
+        Some(&payload.currency),
+        payload.url.as_deref(),
+        payload.stock_status.as_deref(),
+    );
+    if let Err(e) = checked {
+        tracing::warn!("Rejected offer for product {}: {}", product_id, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = begin(&state).await?;
+    lock_product(&mut tx, product_id).await?;
+
+    let retailer = payload.retailer.trim();
+    if retailer_taken(&mut tx, product_id, retailer, None).await? {
+        tracing::warn!(
+            "Product {} already has an offer from '{}'",
+            product_id,
+            retailer
+        );
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let offer = sqlx::query_as::<_, Offer>(
+        r#"
+        INSERT INTO offers (product_id, retailer, price, currency, url, stock_status, last_seen_at)
+        VALUES ($1, $2, $3, $4, $5, COALESCE($6, 'unknown'), COALESCE($7, NOW()))
+        RETURNING id, product_id, retailer, price, currency, url, stock_status, last_seen_at
+        "#,
+    )
+    .bind(product_id)
+    .bind(retailer)
+    .bind(cents_to_price(payload.price))
+    .bind(&payload.currency)
+    .bind(&payload.url)
+    .bind(&payload.stock_status)
+    .bind(payload.last_seen_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create offer for product {}: {}", product_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    AuditEntry::new(&actor, &request_id, "create", "offer", offer.id)
+        .after(&offer)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    let product = update_product_price(&mut tx, &state.exchange_rates, product_id).await?;
+    commit(tx).await?;
+    if let Some(product) = product {
+        state.distributions.invalidate(product.category_id);
+    }
+
+    tracing::info!(
+        "Created offer {} from {} for product {} by {}",
+        offer.id,
+        offer.retailer,
+        product_id,
+        actor
+    );
+
+    Ok((StatusCode::CREATED, Json(OfferResponse { offer })))
+}
+
+pub async fn update_offer(
+    EditorAuth(actor): EditorAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateOffer>,
+) -> Result<Json<OfferResponse>, StatusCode> {
+    let checked = validate_offer(
+        &state.exchange_rates,
+        payload.retailer.as_deref(),
+        payload.price,
+        payload.currency.as_deref(),
+        payload.url.as_deref(),
+        payload.stock_status.as_deref(),
+    );
+    if let Err(e) = checked {
+        tracing::warn!("Rejected update of offer {}: {}", id, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = begin(&state).await?;
+    let before = fetch_offer_for_update(&mut tx, id).await?;
+
+    let retailer = payload.retailer.as_deref().map(str::trim);
+    if let Some(retailer) = retailer
+        && retailer_taken(&mut tx, before.product_id, retailer, Some(id)).await?
+    {
+        tracing::warn!(
+            "Product {} already has an offer from '{}'",
+            before.product_id,
+            retailer
+        );
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let offer = sqlx::query_as::<_, Offer>(
+        r#"
+        UPDATE offers
+        SET retailer = COALESCE($2, retailer),
+            price = COALESCE($3, price),
+            currency = COALESCE($4, currency),
+            url = COALESCE($5, url),
+            stock_status = COALESCE($6, stock_status),
+            last_seen_at = COALESCE($7, NOW()),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, product_id, retailer, price, currency, url, stock_status, last_seen_at
+        "#,
+    )
+    .bind(id)
+    .bind(retailer)
+    .bind(payload.price.map(cents_to_price))
+    .bind(&payload.currency)
+    .bind(&payload.url)
+    .bind(&payload.stock_status)
+    .bind(payload.last_seen_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update offer {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    AuditEntry::new(&actor, &request_id, "update", "offer", id)
+        .before(&before)
+        .after(&offer)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    let product = update_product_price(&mut tx, &state.exchange_rates, offer.product_id).await?;
+    commit(tx).await?;
+    if let Some(product) = product {
+        state.distributions.invalidate(product.category_id);
+    }
+
+    tracing::info!("Updated offer {} by {}", id, actor);
+
+    Ok(Json(OfferResponse { offer }))
+}
+
+pub async fn delete_offer(
+    EditorAuth(actor): EditorAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    let mut tx = begin(&state).await?;
+    let before = fetch_offer_for_update(&mut tx, id).await?;
+
+    sqlx::query("DELETE FROM offers WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete offer {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    AuditEntry::new(&actor, &request_id, "delete", "offer", id)
+        .before(&before)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    let product = update_product_price(&mut tx, &state.exchange_rates, before.product_id).await?;
+    commit(tx).await?;
+    if let Some(product) = product {
+        state.distributions.invalidate(product.category_id);
+    }
+
+    tracing::info!(
+        "Deleted offer {} of product {} by {}",
+        id,
+        before.product_id,
+        actor
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Checks the fields given in a new offer or an update.
+fn validate_offer(
+    rates: &ExchangeRates,
+    retailer: Option<&str>,
+    price: Option<f64>,
+    currency: Option<&str>,
+    url: Option<&str>,
+    stock_status: Option<&str>,
+) -> Result<(), String> {
+    if let Some(retailer) = retailer
+        && (retailer.trim().is_empty() || retailer.trim().chars().count() > 100)
+    {
+        return Err("retailer must be 1 to 100 characters".to_string());
+    }
+    if price.is_some_and(|p| !p.is_finite() || p < 0.0) {
+        return Err("price must be a non-negative number of cents".to_string());
+    }
+    if let Some(currency) = currency
+        && !rates.supports(currency)
+    {
+        return Err(format!("unsupported currency '{}'", currency));
+    }
+    if url.is_some_and(|u| !(u.starts_with("https://") || u.starts_with("http://"))) {
+        return Err("url must be an http or https link".to_string());
+    }
+    if let Some(status) = stock_status
+        && !STOCK_STATUSES.contains(&status)
+    {
+        return Err(format!(
+            "stock_status must be one of {}",
+            STOCK_STATUSES.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+fn cents_to_price(cents: f64) -> BigDecimal {
+    BigDecimal::from(cents as i64) / BigDecimal::from(100)
+}
+
+/// Locks a product that can still carry offers, so its price is refreshed
+/// by one offer write at a time.
+async fn lock_product(
+    tx: &mut Transaction<'static, Postgres>,
+    product_id: i32,
+) -> Result<(), StatusCode> {
+    let locked: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM products WHERE id = $1 AND status NOT IN ('rejected', 'merged') FOR UPDATE",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to lock product {}: {}", product_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    locked.map(|_| ()).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Fetches an offer and locks its product for the rest of the transaction.
+async fn fetch_offer_for_update(
+    tx: &mut Transaction<'static, Postgres>,
+    id: i32,
+) -> Result<Offer, StatusCode> {
+    let product_id: i32 = sqlx::query_scalar("SELECT product_id FROM offers WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch offer {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    lock_product(tx, product_id).await?;
+
+    sqlx::query_as::<_, Offer>(
+        r#"
+        SELECT id, product_id, retailer, price, currency, url, stock_status, last_seen_at
+        FROM offers
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch offer {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    // Deleted or moved while we waited for the lock
+    .filter(|offer| offer.product_id == product_id)
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn retailer_taken(
+    tx: &mut Transaction<'static, Postgres>,
+    product_id: i32,
+    retailer: &str,
+    except: Option<i32>,
+) -> Result<bool, StatusCode> {
+    sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM offers
+            WHERE product_id = $1 AND LOWER(retailer) = LOWER($2)
+                AND ($3::int IS NULL OR id <> $3)
+        )
+        "#,
+    )
+    .bind(product_id)
+    .bind(retailer)
+    .bind(except)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to check offers of product {}: {}", product_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Refreshes the product's price from its offers and records a changed price.
+async fn update_product_price(
+    tx: &mut Transaction<'static, Postgres>,
+    rates: &ExchangeRates,
+    product_id: i32,
+) -> Result<Option<Product>, StatusCode> {
+    let product = refresh_price(tx, rates, product_id).await.map_err(|e| {
+        tracing::error!("Failed to refresh price of product {}: {}", product_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if let Some(product) = &product {
+        record_price(&mut **tx, product, "offer")
+            .await
+            .map_err(price_history_error)?;
+    }
+
+    Ok(product)
+}
@@ -1,30 +1,60 @@
 use axum::extract::State;
 
 use crate::{
-    compare::build_comparison_table,
+    audit::{AuditEntry, RequestId},
+    auth::{ContributorAuth, EditorAuth, Role},
+    compare::{
+        apply_baseline, apply_diff, apply_percentiles, build_common_comparison_table,
+        build_comparison_table, numeric_value, round,
+    },
+    computed::{apply_computed, computed_fields},
+    dimensions::{LengthUnit, Space, convert_rows, parse_specifications, product_fits},
+    duplicates::duplicate_match,
+    energy::{EnergyTerms, cost_rows},
+    etag::{if_match, none_match_satisfied, precondition_failed, product_etag, with_etag},
+    idempotency::{self, Claim, IdempotencyKey},
     models::{
-        Category, CompareQuery, ComparisonResponse, CreateProduct, Product, ProductResponse,
-        ProductsResponse, SearchQuery,
+        Category, CompareMode, CompareQuery, ComparisonOptions, ComparisonResponse, ComparisonRow,
+        ComparisonValue, CreateProduct, CreateProductResponse, DuplicateMatch, PriceHistoryQuery,
+        PriceHistoryResponse, PriceInterval, PricePoint, Product, ProductsResponse, SearchQuery,
+        SimilarProductsResponse, SimilarQuery, UpdateProduct,
     },
-    routes::AppState,
+    prices::{AVERAGE_DAYS, compare_to_average, time_weighted_average},
+    revisions::record_revision,
+    routes::{AppState, audit_error, begin, commit, idempotency_error, revision_error, slug_error},
+    schemas::{EnergyProfile, WeightProfile},
+    scoring::weighted_score,
+    similarity::nearest_products,
+    slugs::{assign_slug, follow_rename},
+    stats::{CategoryDistribution, field_value},
 };
 
 use axum::{
     Json,
-    extract::{Path, Query},
-    http::StatusCode,
+    extract::{Path, Query, RawQuery},
+    http::{HeaderMap, StatusCode, header::ETAG},
+    response::{IntoResponse, Redirect, Response},
 };
 
 use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgExecutor;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 pub async fn list_all_products(
     State(state): State<AppState>,
 ) -> Result<Json<ProductsResponse>, StatusCode> {
     let mut products = sqlx::query_as::<_, Product>(
         r#"
-        SELECT p.id, p.category_id, c.name as category_name, p.name, p.manufacturer, p.model, p.specifications, p.price
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
         FROM products p
         JOIN categories c ON p.category_id = c.id
+        WHERE c.archived_at IS NULL AND p.status = 'approved'
         ORDER BY p.name
         "#,
     )
@@ -35,7 +65,8 @@ pub async fn list_all_products(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    products.sort_by(|a, b| a.id.cmp(&b.id));
+    products.sort_by_key(|p| p.id);
+    with_computed(&state.db, &mut products).await?;
 
     Ok(Json(ProductsResponse { products }))
 }
@@ -43,41 +74,144 @@ pub async fn list_all_products(
 pub async fn get_product(
     State(state): State<AppState>,
     Path(id): Path<i32>,
-) -> Result<Json<ProductResponse>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    product_response(&state, id, &headers).await
+}
+
+/// Looks a product up by its current slug or any slug it had before.
+pub async fn get_product_by_slug(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let id = sqlx::query_scalar::<_, i32>("SELECT product_id FROM product_slugs WHERE slug = $1")
+        .bind(&slug)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up product slug '{}': {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    product_response(&state, id, &headers).await
+}
+
+async fn product_response(
+    state: &AppState,
+    id: i32,
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
     let product = sqlx::query_as::<_, Product>(
         r#"
-        SELECT p.id, p.category_id, c.name as category_name, p.name, p.manufacturer, p.model, p.specifications, p.price
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
         FROM products p
         JOIN categories c ON p.category_id = c.id
-        WHERE p.id = $1
+        WHERE p.id = $1 AND c.archived_at IS NULL AND p.status = 'approved'
         "#,
     )
     .bind(id)
-    .fetch_one(&state.db)
+    .fetch_optional(&state.db)
     .await
     .map_err(|e| {
         tracing::error!("Failed to fetch product {}: {}", id, e);
-        StatusCode::NOT_FOUND
+        StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok(Json(ProductResponse { product }))
+    let Some(mut product) = product else {
+        // Products merged into another one redirect to the survivor
+        let merged_into = merged_into(state, id).await?;
+        return Ok(
+            Redirect::permanent(&format!("/api/v1/products/{}", merged_into)).into_response(),
+        );
+    };
+
+    let etag = product_etag(&product);
+    if none_match_satisfied(headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+    }
+
+    with_computed(&state.db, std::slice::from_mut(&mut product)).await?;
+
+    Ok(with_etag(StatusCode::OK, product))
 }
 
 pub async fn create_product(
+    ContributorAuth(actor): ContributorAuth,
+    request_id: RequestId,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
     State(state): State<AppState>,
-    Json(payload): Json<CreateProduct>,
-) -> Result<(StatusCode, Json<ProductResponse>), StatusCode> {
+    Json(mut payload): Json<CreateProduct>,
+) -> Result<Response, StatusCode> {
+    if idempotency_key
+        .as_deref()
+        .is_some_and(|key| !idempotency::valid_key(key))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = begin(&state).await?;
+
+    // A retry must replay the first response rather than hit the name check below
+    if let Some(key) = &idempotency_key {
+        let fingerprint = idempotency::fingerprint("POST /products", &payload);
+        match idempotency::claim(&mut tx, &actor, key, &fingerprint)
+            .await
+            .map_err(idempotency_error)?
+        {
+            Claim::New => {}
+            Claim::Replay(response) => {
+                tracing::info!("Replayed product creation for idempotency key '{}'", key);
+                return Ok(response);
+            }
+            Claim::Mismatch => {
+                tracing::warn!("Idempotency key '{}' reused with a different request", key);
+                return Err(StatusCode::UNPROCESSABLE_ENTITY);
+            }
+        }
+    }
+
+    // Archiving takes the category's row lock, so it waits for this product
+    let archived: Option<bool> = sqlx::query_scalar(
+        "SELECT archived_at IS NOT NULL FROM categories WHERE id = $1 FOR SHARE",
+    )
+    .bind(payload.category_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch category {}: {}", payload.category_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    match archived {
+        None => {
+            tracing::warn!(
+                "Rejected product in unknown category {}",
+                payload.category_id
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Some(true) => {
+            tracing::warn!(
+                "Rejected product in archived category {}",
+                payload.category_id
+            );
+            return Err(StatusCode::CONFLICT);
+        }
+        Some(false) => {}
+    }
+
     // Check if product with the same name already exists
     let existing = sqlx::query_as::<_, Product>(
         r#"
-        SELECT p.id, p.category_id, c.name as category_name, p.name, p.manufacturer, p.model, p.specifications, p.price
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
         FROM products p
         JOIN categories c ON p.category_id = c.id
-        WHERE LOWER(p.name) = LOWER($1)
+        WHERE LOWER(p.name) = LOWER($1) AND p.status NOT IN ('rejected', 'merged')
         "#,
     )
     .bind(&payload.name)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!("Failed to check for existing product: {}", e);
@@ -89,17 +223,24 @@ pub async fn create_product(
         return Err(StatusCode::CONFLICT);
     }
 
-    let price = payload
-        .price
-        .map(|p| BigDecimal::from(p as i64) / BigDecimal::from(100));
+    prepare_specifications(
+        &mut *tx,
+        payload.category_id,
+        payload.specifications.as_mut(),
+    )
+    .await?;
 
-    let product = sqlx::query_as::<_, Product>(
+    // Submissions from contributors wait for a moderator, editors publish directly
+    let approved = actor.role().is_some_and(|role| role >= Role::Editor);
+    let status = if approved { "approved" } else { "pending" };
+
+    let mut product = sqlx::query_as::<_, Product>(
         r#"
-        INSERT INTO products (category_id, name, manufacturer, model, specifications, price)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO products (category_id, name, slug, manufacturer, model, specifications, status, submitted_by)
+        VALUES ($1, $2, '~' || gen_random_uuid(), $3, $4, $5, $6, $7)
         RETURNING id, category_id, 
             (SELECT name FROM categories WHERE id = $1) as category_name,
-            name, manufacturer, model, specifications, price
+            name, slug, manufacturer, model, specifications, price, currency, price_eur, offer_count, version
         "#,
     )
     .bind(payload.category_id)
@@ -107,28 +248,209 @@ pub async fn create_product(
     .bind(&payload.manufacturer)
     .bind(&payload.model)
     .bind(&payload.specifications)
-    .bind(price)
-    .fetch_one(&state.db)
+    .bind(status)
+    .bind(actor.user_id())
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!("Failed to create product: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok((StatusCode::CREATED, Json(ProductResponse { product })))
+    // Inserted with a placeholder, as the slug is only ours once claimed
+    product.slug = assign_slug(&mut tx, product.id, &product.name)
+        .await
+        .map_err(slug_error)?;
+
+    record_revision(&mut *tx, &product, &actor, "create")
+        .await
+        .map_err(revision_error)?;
+
+    let possible_duplicates = find_duplicates(&mut tx, &product).await?;
+    if !possible_duplicates.is_empty() {
+        tracing::warn!(
+            "Product '{}' looks like a duplicate of {:?}",
+            product.name,
+            possible_duplicates
+                .iter()
+                .map(|d| d.product.id)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    AuditEntry::new(&actor, &request_id, "create", "product", product.id)
+        .after(&product)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    with_computed(&mut *tx, std::slice::from_mut(&mut product)).await?;
+
+    let status_code = if approved {
+        StatusCode::CREATED
+    } else {
+        StatusCode::ACCEPTED
+    };
+    let response = CreateProductResponse {
+        product,
+        possible_duplicates,
+    };
+
+    if let Some(key) = &idempotency_key {
+        idempotency::store_response(&mut *tx, &actor, key, status_code, &response)
+            .await
+            .map_err(idempotency_error)?;
+    }
+
+    commit(tx).await?;
+    state.distributions.invalidate(response.product.category_id);
+
+    tracing::info!(
+        "Created product {} ({}) as {} by {}",
+        response.product.id,
+        response.product.name,
+        status,
+        actor
+    );
+
+    Ok((status_code, Json(response)).into_response())
+}
+
+pub async fn update_product(
+    EditorAuth(actor): EditorAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+    Json(mut payload): Json<UpdateProduct>,
+) -> Result<Response, StatusCode> {
+    if payload.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if payload
+        .specifications
+        .as_ref()
+        .is_some_and(|specs| !specs.is_object())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = begin(&state).await?;
+
+    let before = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.id = $1
+        FOR UPDATE OF p
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Checked under the row lock so concurrent editors can't both pass
+    match if_match(&headers, &product_etag(&before)) {
+        None => return Err(StatusCode::PRECONDITION_REQUIRED),
+        Some(false) => return Ok(precondition_failed(before)),
+        Some(true) => {}
+    }
+
+    prepare_specifications(
+        &mut *tx,
+        before.category_id,
+        payload.specifications.as_mut(),
+    )
+    .await?;
+
+    let mut product = sqlx::query_as::<_, Product>(
+        r#"
+        UPDATE products p
+        SET name = COALESCE($2, p.name),
+            manufacturer = COALESCE($3, p.manufacturer),
+            model = COALESCE($4, p.model),
+            specifications = CASE
+                WHEN $5::jsonb IS NULL THEN p.specifications
+                ELSE COALESCE(p.specifications, '{}'::jsonb) || $5::jsonb
+            END,
+            version = p.version + 1,
+            updated_at = NOW()
+        FROM categories c
+        WHERE p.id = $1 AND c.id = p.category_id
+        RETURNING p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        "#,
+    )
+    .bind(id)
+    .bind(&payload.name)
+    .bind(&payload.manufacturer)
+    .bind(&payload.model)
+    .bind(&payload.specifications)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    follow_rename(&mut tx, &mut product)
+        .await
+        .map_err(slug_error)?;
+
+    record_revision(&mut *tx, &product, &actor, "update")
+        .await
+        .map_err(revision_error)?;
+
+    AuditEntry::new(&actor, &request_id, "update", "product", product.id)
+        .before(&before)
+        .after(&product)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+    state.distributions.invalidate(product.category_id);
+
+    tracing::info!("Updated product {} by {}", product.id, actor);
+
+    with_computed(&state.db, std::slice::from_mut(&mut product)).await?;
+
+    Ok(with_etag(StatusCode::OK, product))
 }
 
 pub async fn search_products(
     State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
 ) -> Result<Json<ProductsResponse>, StatusCode> {
+    let products = find_products(&state, &query).await?;
+
+    Ok(Json(ProductsResponse { products }))
+}
+
+/// Approved products matching the search filters, ordered by id unless
+/// `sort` says otherwise.
+pub(crate) async fn find_products(
+    state: &AppState,
+    query: &SearchQuery,
+) -> Result<Vec<Product>, StatusCode> {
+    let space = search_space(query).map_err(|e| {
+        tracing::warn!("Rejected search: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
     // Build base query
     let mut sql = String::from(
         r#"
-        SELECT DISTINCT p.id, p.category_id, c.name as category_name, p.name, p.manufacturer, p.model, p.specifications, p.price
+        SELECT DISTINCT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
         FROM products p
-        LEFT JOIN categories c ON p.category_id = c.id
-        WHERE 1=1
+        JOIN categories c ON p.category_id = c.id
+        WHERE c.archived_at IS NULL AND p.status = 'approved'
         "#,
     );
 
@@ -162,11 +484,11 @@ pub async fn search_products(
     }
     if min_price_decimal.is_some() {
         param_count += 1;
-        sql.push_str(&format!(" AND p.price >= ${}", param_count));
+        sql.push_str(&format!(" AND p.price_eur >= ${}", param_count));
     }
     if max_price_decimal.is_some() {
         param_count += 1;
-        sql.push_str(&format!(" AND p.price <= ${}", param_count));
+        sql.push_str(&format!(" AND p.price_eur <= ${}", param_count));
     }
 
     sql.push_str(" ORDER BY p.name");
@@ -195,9 +517,27 @@ pub async fn search_products(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    products.sort_by(|a, b| a.id.cmp(&b.id));
+    products.sort_by_key(|p| p.id);
 
-    Ok(Json(ProductsResponse { products }))
+    let categories = product_categories(&state.db, &products).await?;
+    for category in &categories {
+        apply_computed(category, &mut products);
+    }
+
+    if let Some(space) = space {
+        products.retain(|p| {
+            categories
+                .iter()
+                .find(|c| c.id == p.category_id)
+                .is_some_and(|c| product_fits(c, p, &space))
+        });
+    }
+
+    if let Some(sort) = &query.sort {
+        sort_products(&mut products, sort);
+    }
+
+    Ok(products)
 }
 
 pub async fn compare_products(
@@ -205,27 +545,297 @@ pub async fn compare_products(
     Query(query): Query<CompareQuery>,
 ) -> Result<Json<ComparisonResponse>, StatusCode> {
     // Parse product IDs from comma-separated string
-    let product_ids: Vec<i32> = query
+    let mut product_ids: Vec<i32> = query
         .ids
         .split(',')
         .filter_map(|s| s.trim().parse().ok())
         .collect();
 
+    let slugs: Vec<String> = query
+        .slugs
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    product_ids.extend(resolve_slugs(&state, &slugs).await?);
+
+    let options = ComparisonOptions {
+        mode: query.mode,
+        diff: query.diff,
+        baseline: query.baseline,
+        profile: query.profile,
+        units: query.units,
+        tariff: query.tariff,
+        hours_per_day: query.hours_per_day,
+        cycles_per_week: query.cycles_per_week,
+        years: query.years,
+    };
+
+    Ok(Json(
+        build_comparison(&state, &product_ids, &options).await?,
+    ))
+}
+
+pub async fn get_similar_products(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<SimilarQuery>,
+) -> Result<Json<SimilarProductsResponse>, StatusCode> {
+    const DEFAULT_LIMIT: usize = 5;
+    const MAX_LIMIT: usize = 50;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let mut product = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.id = $1 AND c.archived_at IS NULL AND p.status = 'approved'
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let category = sqlx::query_as::<_, Category>(
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE id = $1",
+    )
+    .bind(product.category_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch category: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut candidates = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.category_id = $1 AND c.archived_at IS NULL AND p.status = 'approved'
+        "#,
+    )
+    .bind(product.category_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch products for category {}: {}", category.slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    apply_computed(&category, std::slice::from_mut(&mut product));
+    apply_computed(&category, &mut candidates);
+
+    let similar = nearest_products(&category, &product, &candidates, query.include_price, limit);
+
+    Ok(Json(SimilarProductsResponse { product, similar }))
+}
+
+pub async fn get_price_history(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    RawQuery(raw_query): RawQuery,
+    Query(query): Query<PriceHistoryQuery>,
+) -> Result<Response, StatusCode> {
+    let current: Option<(Option<f64>, Option<String>, Option<f64>)> = sqlx::query_as(
+        "SELECT price::float8, currency, price_eur::float8 FROM products WHERE id = $1 AND status = 'approved'",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some((current, currency, current_eur)) = current else {
+        // The history of a merged product lives on with the survivor
+        let merged_into = merged_into(&state, id).await?;
+        let query = raw_query.map(|q| format!("?{}", q)).unwrap_or_default();
+        return Ok(Redirect::permanent(&format!(
+            "/api/v1/products/{}/prices{}",
+            merged_into, query
+        ))
+        .into_response());
+    };
+
+    let history = sqlx::query_as::<_, PricePoint>(
+        r#"
+        SELECT recorded_at AS at, price::float8 AS price, currency, price_eur::float8 AS price_eur,
+            NULL::float8 AS low_eur, NULL::float8 AS high_eur, source
+        FROM price_history
+        WHERE product_id = $1
+        ORDER BY recorded_at, id
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch price history of product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let bucket = match query.interval {
+        PriceInterval::Raw => None,
+        PriceInterval::Hour => Some("hour"),
+        PriceInterval::Day => Some("day"),
+        PriceInterval::Week => Some("week"),
+        PriceInterval::Month => Some("month"),
+    };
+    let in_range = |at: &DateTime<Utc>| {
+        query.since.is_none_or(|since| *at >= since) && query.until.is_none_or(|until| *at < until)
+    };
+
+    let points = match bucket {
+        None => history
+            .iter()
+            .filter(|p| in_range(&p.at))
+            .cloned()
+            .collect(),
+        Some(bucket) => sqlx::query_as::<_, PricePoint>(
+            r#"
+            SELECT date_trunc($4, recorded_at) AS at,
+                ((array_agg(price ORDER BY recorded_at DESC, id DESC))[1])::float8 AS price,
+                (array_agg(currency ORDER BY recorded_at DESC, id DESC))[1] AS currency,
+                ((array_agg(price_eur ORDER BY recorded_at DESC, id DESC))[1])::float8 AS price_eur,
+                MIN(price_eur)::float8 AS low_eur,
+                MAX(price_eur)::float8 AS high_eur,
+                NULL::text AS source
+            FROM price_history
+            WHERE product_id = $1
+                AND ($2::timestamptz IS NULL OR recorded_at >= $2)
+                AND ($3::timestamptz IS NULL OR recorded_at < $3)
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )
+        .bind(id)
+        .bind(query.since)
+        .bind(query.until)
+        .bind(bucket)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to downsample price history of product {}: {}",
+                id,
+                e
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?,
+    };
+
+    // Prices in different currencies compare in euros. The earliest point wins
+    // ties, so a low is dated when it was first reached
+    let in_euros: Vec<(&PricePoint, f64)> = history
+        .iter()
+        .filter_map(|p| Some((p, p.price_eur?)))
+        .collect();
+    let all_time_low = in_euros
+        .iter()
+        .reduce(|low, p| if p.1 < low.1 { p } else { low })
+        .map(|(p, _)| (*p).clone());
+    let all_time_high = in_euros
+        .iter()
+        .reduce(|high, p| if p.1 > high.1 { p } else { high })
+        .map(|(p, _)| (*p).clone());
+
+    let series: Vec<(DateTime<Utc>, f64)> = in_euros.iter().map(|(p, eur)| (p.at, *eur)).collect();
+    let average = time_weighted_average(&series, Utc::now(), AVERAGE_DAYS);
+    let comparison = current_eur
+        .zip(average)
+        .and_then(|(current, average)| compare_to_average(current, average));
+
+    Ok(Json(PriceHistoryResponse {
+        product_id: id,
+        current,
+        currency,
+        current_eur,
+        all_time_low,
+        all_time_high,
+        average_30_days_eur: average.map(round),
+        vs_average_percent: comparison.map(|(percent, _)| round(percent)),
+        indicator: comparison.map(|(_, indicator)| indicator),
+        interval: query.interval,
+        points,
+    })
+    .into_response())
+}
+
+/// The product a merged product was merged into, or 404 for any other product.
+async fn merged_into(state: &AppState, id: i32) -> Result<i32, StatusCode> {
+    sqlx::query_scalar::<_, i32>(
+        "SELECT merged_into FROM products WHERE id = $1 AND status = 'merged'",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up merged product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Ids of the products with the given slugs. Slugs of merged products resolve
+/// to the product they were merged into.
+pub(crate) async fn resolve_slugs(
+    state: &AppState,
+    slugs: &[String],
+) -> Result<Vec<i32>, StatusCode> {
+    if slugs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_scalar::<_, i32>(
+        r#"
+        SELECT COALESCE(p.merged_into, p.id)
+        FROM product_slugs s
+        JOIN products p ON p.id = s.product_id
+        WHERE s.slug = ANY($1)
+        "#,
+    )
+    .bind(slugs)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to resolve product slugs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Fetches the products and builds their comparison table, for both ad-hoc and
+/// saved comparisons.
+pub(crate) async fn build_comparison(
+    state: &AppState,
+    product_ids: &[i32],
+    options: &ComparisonOptions,
+) -> Result<ComparisonResponse, StatusCode> {
     if product_ids.is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
 
     // Fetch products
-    let products = sqlx::query_as::<_, Product>(
+    let mut products = sqlx::query_as::<_, Product>(
         r#"
-        SELECT p.id, p.category_id, c.name as category_name, p.name, p.manufacturer, p.model, p.specifications, p.price
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
         FROM products p
         JOIN categories c ON p.category_id = c.id
-        WHERE p.id = ANY($1)
+        WHERE p.id = ANY($1) AND c.archived_at IS NULL AND p.status = 'approved'
         ORDER BY p.id
         "#,
     )
-    .bind(&product_ids)
+    .bind(product_ids)
     .fetch_all(&state.db)
     .await
     .map_err(|e| {
@@ -237,30 +847,528 @@ pub async fn compare_products(
         return Err(StatusCode::NOT_FOUND);
     }
 
-    // Ensure all products are from the same category
-    let category_id = products[0].category_id;
-    if !products.iter().all(|p| p.category_id == category_id) {
+    with_computed(&state.db, &mut products).await?;
+
+    // A baseline must be one of the compared products
+    let baseline_id = options.baseline.unwrap_or(products[0].id);
+    if !products.iter().any(|p| p.id == baseline_id) {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    // Fetch category with schema
+    let energy_terms = options
+        .tariff
+        .map(|tariff| {
+            EnergyTerms::new(
+                tariff,
+                options.hours_per_day,
+                options.cycles_per_week,
+                options.years,
+            )
+        })
+        .transpose()
+        .map_err(|e| {
+            tracing::warn!("Rejected energy terms for comparison: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let units = match options.units.as_deref() {
+        None => None,
+        Some("metric") => Some(LengthUnit::Cm),
+        Some("imperial") => Some(LengthUnit::In),
+        Some(units) => {
+            tracing::warn!("Rejected comparison units '{}'", units);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let mut response = if options.mode == CompareMode::Common {
+        let mut category_ids: Vec<i32> = products.iter().map(|p| p.category_id).collect();
+        category_ids.sort_unstable();
+        category_ids.dedup();
+
+        let categories = sqlx::query_as::<_, Category>(
+            "SELECT id, name, slug, description, specification_schema FROM categories WHERE id = ANY($1) ORDER BY id",
+        )
+        .bind(&category_ids)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch categories: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        let (comparison_table, excluded_fields) =
+            build_common_comparison_table(&categories, &products);
+
+        ComparisonResponse {
+            category: None,
+            categories,
+            products,
+            comparison_table,
+            excluded_fields,
+            baseline_id: None,
+            missing_values: Vec::new(),
+            baseline_summary: Vec::new(),
+        }
+    } else {
+        // Ensure all products are from the same category
+        let category_id = products[0].category_id;
+        if !products.iter().all(|p| p.category_id == category_id) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        // Fetch category with schema
+        let category = sqlx::query_as::<_, Category>(
+            "SELECT id, name, slug, description, specification_schema FROM categories WHERE id = $1",
+        )
+        .bind(category_id)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch category: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        // Build comparison table
+        let comparison_table = build_comparison_table(&category, &products);
+
+        ComparisonResponse {
+            category: Some(category),
+            categories: Vec::new(),
+            products,
+            comparison_table,
+            excluded_fields: Vec::new(),
+            baseline_id: None,
+            missing_values: Vec::new(),
+            baseline_summary: Vec::new(),
+        }
+    };
+
+    // Running costs go right after the price
+    if let Some(terms) = &energy_terms {
+        let category_ids: Vec<i32> = response
+            .category
+            .iter()
+            .chain(&response.categories)
+            .map(|c| c.id)
+            .collect();
+        let profiles = energy_profiles(state, &category_ids).await?;
+
+        if !profiles.is_empty() {
+            let position = response
+                .comparison_table
+                .iter()
+                .position(|row| row.field == "price")
+                .map_or(response.comparison_table.len(), |i| i + 1);
+            response.comparison_table.splice(
+                position..position,
+                cost_rows(&profiles, terms, &response.products),
+            );
+        }
+    }
+
+    let mut distributions = HashMap::new();
+    for category in response.category.iter().chain(&response.categories) {
+        distributions.insert(category.id, category_distribution(state, category).await?);
+    }
+    apply_percentiles(
+        &mut response.comparison_table,
+        &response.products,
+        &distributions,
+    );
+
+    if let Some(unit) = units {
+        for category in response.category.iter().chain(&response.categories) {
+            convert_rows(
+                category,
+                &response.products,
+                &mut response.comparison_table,
+                unit,
+            );
+        }
+    }
+
+    // The profile's score goes after the price and offers
+    if let Some(profile) = &options.profile {
+        let categories: Vec<&Category> = response
+            .category
+            .iter()
+            .chain(&response.categories)
+            .collect();
+        let category_ids: Vec<i32> = categories.iter().map(|c| c.id).collect();
+        let profiles = weight_profiles(state, &category_ids).await?;
+
+        let mut scores = HashMap::new();
+        for category in categories {
+            let weights = profiles
+                .get(&category.id)
+                .and_then(|p| p.get(profile))
+                .ok_or_else(|| {
+                    tracing::warn!(
+                        "Unknown weight profile '{}' for category {}",
+                        profile,
+                        category.slug
+                    );
+                    StatusCode::BAD_REQUEST
+                })?;
+            let distribution = &distributions[&category.id];
+            for product in response
+                .products
+                .iter()
+                .filter(|p| p.category_id == category.id)
+            {
+                let score = weighted_score(category, distribution, product, weights);
+                scores.insert(product.id, score.map(|s| round(s.score)));
+            }
+        }
+
+        let position = response
+            .comparison_table
+            .iter()
+            .position(|row| row.field == "offer_count")
+            .map_or(response.comparison_table.len(), |i| i + 1);
+        response.comparison_table.insert(
+            position,
+            ComparisonRow {
+                field: "profile_score".to_string(),
+                label: format!("Score ({})", profile),
+                unit: "".to_string(),
+                higher_is_better: Some(true),
+                values: response
+                    .products
+                    .iter()
+                    .map(|p| ComparisonValue {
+                        product_id: p.id,
+                        product_name: p.name.clone(),
+                        value: json!(scores.get(&p.id).copied().flatten()),
+                        delta: None,
+                        delta_percent: None,
+                        verdict: None,
+                        category_percentile: None,
+                    })
+                    .collect(),
+            },
+        );
+    }
+
+    // Diff mode measures against the first product unless told otherwise
+    if options.diff || options.baseline.is_some() {
+        response.baseline_summary = apply_baseline(
+            &mut response.comparison_table,
+            &response.products,
+            baseline_id,
+        );
+        response.baseline_id = Some(baseline_id);
+    }
+
+    if options.diff {
+        let rows = std::mem::take(&mut response.comparison_table);
+        let (rows, missing_values) = apply_diff(rows, &response.products);
+        response.comparison_table = rows;
+        response.missing_values = missing_values;
+    }
+
+    Ok(response)
+}
+
+/// Distribution of the category's approved products, built on first use and
+/// cached until one of them changes.
+pub(crate) async fn category_distribution(
+    state: &AppState,
+    category: &Category,
+) -> Result<Arc<CategoryDistribution>, StatusCode> {
+    if let Some(distribution) = state.distributions.get(category.id) {
+        return Ok(distribution);
+    }
+
+    // Read before the products, see DistributionCache
+    let generation = state.distributions.generation(category.id);
+
+    let mut products = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.category_id = $1 AND c.archived_at IS NULL AND p.status = 'approved'
+        "#,
+    )
+    .bind(category.id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch products for category {}: {}", category.slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    apply_computed(category, &mut products);
+
+    let distribution = Arc::new(CategoryDistribution::new(category, &products));
+    state
+        .distributions
+        .insert(category.id, generation, distribution.clone());
+
+    Ok(distribution)
+}
+
+/// Fills in the computed fields of products from any number of categories.
+pub(crate) async fn with_computed(
+    executor: impl PgExecutor<'_>,
+    products: &mut [Product],
+) -> Result<(), StatusCode> {
+    for category in &product_categories(executor, products).await? {
+        apply_computed(category, products);
+    }
+
+    Ok(())
+}
+
+/// Categories of the given products.
+async fn product_categories(
+    executor: impl PgExecutor<'_>,
+    products: &[Product],
+) -> Result<Vec<Category>, StatusCode> {
+    let mut category_ids: Vec<i32> = products.iter().map(|p| p.category_id).collect();
+    category_ids.sort_unstable();
+    category_ids.dedup();
+
+    if category_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_as::<_, Category>(
+        "SELECT id, name, slug, description, specification_schema FROM categories WHERE id = ANY($1)",
+    )
+    .bind(&category_ids)
+    .fetch_all(executor)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch categories: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Energy profiles of the categories that have one.
+pub(crate) async fn energy_profiles(
+    state: &AppState,
+    category_ids: &[i32],
+) -> Result<HashMap<i32, EnergyProfile>, StatusCode> {
+    let rows: Vec<(i32, serde_json::Value)> = sqlx::query_as(
+        "SELECT id, energy_profile FROM categories WHERE id = ANY($1) AND energy_profile IS NOT NULL",
+    )
+    .bind(category_ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch energy profiles: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    rows.into_iter()
+        .map(|(id, profile)| {
+            serde_json::from_value(profile)
+                .map(|profile| (id, profile))
+                .map_err(|e| {
+                    tracing::error!("Invalid energy profile for category {}: {}", id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })
+        })
+        .collect()
+}
+
+/// Weight profiles of the categories, by name.
+pub(crate) async fn weight_profiles(
+    state: &AppState,
+    category_ids: &[i32],
+) -> Result<HashMap<i32, BTreeMap<String, WeightProfile>>, StatusCode> {
+    let rows: Vec<(i32, serde_json::Value)> =
+        sqlx::query_as("SELECT id, weight_profiles FROM categories WHERE id = ANY($1)")
+            .bind(category_ids)
+            .fetch_all(&state.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch weight profiles: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+    rows.into_iter()
+        .map(|(id, profiles)| {
+            serde_json::from_value(profiles)
+                .map(|profiles| (id, profiles))
+                .map_err(|e| {
+                    tracing::error!("Invalid weight profiles for category {}: {}", id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })
+        })
+        .collect()
+}
+
+/// Checks specifications before they are written: computed fields are derived
+/// on every read so they may not be set, and dimensions are parsed into their
+/// structured form.
+pub(crate) async fn prepare_specifications(
+    executor: impl PgExecutor<'_>,
+    category_id: i32,
+    specifications: Option<&mut serde_json::Value>,
+) -> Result<(), StatusCode> {
+    let Some(specifications) = specifications.filter(|s| s.is_object()) else {
+        return Ok(());
+    };
+
     let category = sqlx::query_as::<_, Category>(
         "SELECT id, name, slug, description, specification_schema FROM categories WHERE id = $1",
     )
     .bind(category_id)
-    .fetch_one(&state.db)
+    .fetch_optional(executor)
     .await
     .map_err(|e| {
         tracing::error!("Failed to fetch category: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    // Build comparison table
-    let comparison_table = build_comparison_table(&category, &products);
+    let Some(category) = category else {
+        return Ok(());
+    };
+
+    let computed: Vec<String> = computed_fields(&category)
+        .into_iter()
+        .map(|(field, _)| field)
+        .filter(|field| specifications.get(field).is_some())
+        .collect();
+
+    if !computed.is_empty() {
+        tracing::warn!(
+            "Rejected write to computed fields of '{}': {}",
+            category.slug,
+            computed.join(", ")
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    parse_specifications(&category, specifications).map_err(|e| {
+        tracing::warn!("Rejected specifications for '{}': {}", category.slug, e);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+/// The space products must fit into, when the search gives any maximum
+/// measurement.
+fn search_space(query: &SearchQuery) -> Result<Option<Space>, String> {
+    if query.fits_width.is_none() && query.fits_height.is_none() && query.fits_depth.is_none() {
+        return Ok(None);
+    }
+
+    let unit = match &query.fits_unit {
+        Some(unit) => LengthUnit::parse(unit).ok_or_else(|| format!("unknown unit '{}'", unit))?,
+        None => LengthUnit::default(),
+    };
+
+    let clearance = query.clearance.unwrap_or(0.0);
+    if !clearance.is_finite() || clearance < 0.0 {
+        return Err("clearance must be a non-negative number".to_string());
+    }
+
+    let measurements = [query.fits_width, query.fits_height, query.fits_depth];
+    if measurements
+        .iter()
+        .flatten()
+        .any(|m| !m.is_finite() || *m <= 0.0)
+    {
+        return Err("maximum measurements must be positive".to_string());
+    }
 
-    Ok(Json(ComparisonResponse {
-        category,
-        products,
-        comparison_table,
+    Ok(Some(Space {
+        width: query.fits_width,
+        height: query.fits_height,
+        depth: query.fits_depth,
+        unit,
+        clearance,
     }))
 }
+
+/// Sorts by `name`, `price` or a specification field, descending with a `-`
+/// prefix. Numbers sort before text and products without a value go last.
+fn sort_products(products: &mut [Product], sort: &str) {
+    let (field, descending) = match sort.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (sort, false),
+    };
+
+    let sort_value = |product: &Product| -> Option<Result<f64, String>> {
+        let value = if field == "name" {
+            json!(product.name)
+        } else {
+            field_value(product, field)
+        };
+
+        match value {
+            serde_json::Value::Null => None,
+            _ => match numeric_value(&value) {
+                Some(number) => Some(Ok(number)),
+                None => Some(Err(value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), |s| s.to_lowercase()))),
+            },
+        }
+    };
+
+    products.sort_by(|a, b| match (sort_value(a), sort_value(b)) {
+        (Some(a), Some(b)) => {
+            let ordering = match (a, b) {
+                (Ok(a), Ok(b)) => a.total_cmp(&b),
+                (Err(a), Err(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+            };
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+}
+
+/// Most similar existing products in the same category, for warning about
+/// near-duplicates that the exact name check lets through.
+async fn find_duplicates(
+    conn: &mut sqlx::PgConnection,
+    product: &Product,
+) -> Result<Vec<DuplicateMatch>, StatusCode> {
+    const MAX_DUPLICATES: usize = 5;
+
+    let candidates = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.category_id = $1 AND p.id <> $2 AND p.status NOT IN ('rejected', 'merged')
+        "#,
+    )
+    .bind(product.category_id)
+    .bind(product.id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch products for duplicate check: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut duplicates: Vec<DuplicateMatch> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            duplicate_match(&candidate, product).map(|(similarity, reason)| DuplicateMatch {
+                product: candidate,
+                similarity,
+                reason,
+            })
+        })
+        .collect();
+
+    duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    duplicates.truncate(MAX_DUPLICATES);
+
+    Ok(duplicates)
+}
@@ -0,0 +1,194 @@
+use axum::extract::State;
+
+use crate::{
+    audit::{AuditEntry, RequestId},
+    auth::EditorAuth,
+    diff::product_differences,
+    etag::{if_match, precondition_failed, product_etag, with_etag},
+    models::{
+        Product, ProductRevision, RevisionDiffQuery, RevisionDiffResponse, RevisionsResponse,
+    },
+    revisions::record_revision,
+    routes::{AppState, audit_error, begin, commit, revision_error, slug_error},
+    slugs::follow_rename,
+};
+
+use axum::{
+    Json,
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+
+pub async fn list_revisions(
+    EditorAuth(_actor): EditorAuth,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<RevisionsResponse>, StatusCode> {
+    let revisions = sqlx::query_as::<_, ProductRevision>(
+        r#"
+        SELECT product_id, revision, action, actor_type, actor_id, actor_name, created_at, snapshot
+        FROM product_revisions
+        WHERE product_id = $1
+        ORDER BY revision DESC
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch revisions of product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if revisions.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(RevisionsResponse { revisions }))
+}
+
+pub async fn get_revision(
+    EditorAuth(_actor): EditorAuth,
+    State(state): State<AppState>,
+    Path((id, rev)): Path<(i32, i32)>,
+) -> Result<Json<ProductRevision>, StatusCode> {
+    fetch_revision(&state, id, rev).await.map(Json)
+}
+
+pub async fn diff_revisions(
+    EditorAuth(_actor): EditorAuth,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<RevisionDiffQuery>,
+) -> Result<Json<RevisionDiffResponse>, StatusCode> {
+    let from = fetch_revision(&state, id, query.from).await?;
+    let to = fetch_revision(&state, id, query.to).await?;
+
+    Ok(Json(RevisionDiffResponse {
+        product_id: id,
+        from: query.from,
+        to: query.to,
+        differences: product_differences(&from.snapshot, &to.snapshot),
+    }))
+}
+
+pub async fn restore_revision(
+    EditorAuth(actor): EditorAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path((id, rev)): Path<(i32, i32)>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let mut tx = begin(&state).await?;
+
+    let before = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        FROM products p
+        JOIN categories c ON p.category_id = c.id
+        WHERE p.id = $1
+        FOR UPDATE OF p
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch product {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    match if_match(&headers, &product_etag(&before)) {
+        None => return Err(StatusCode::PRECONDITION_REQUIRED),
+        Some(false) => return Ok(precondition_failed(before)),
+        Some(true) => {}
+    }
+
+    let revision = fetch_revision(&state, id, rev).await?;
+    let snapshot = revision.snapshot;
+
+    // The price follows the offers, which revisions don't cover
+    let mut product = sqlx::query_as::<_, Product>(
+        r#"
+        UPDATE products p
+        SET category_id = $2,
+            name = $3,
+            manufacturer = $4,
+            model = $5,
+            specifications = $6,
+            version = p.version + 1,
+            updated_at = NOW()
+        FROM categories c
+        WHERE p.id = $1 AND c.id = $2
+        RETURNING p.id, p.category_id, c.name as category_name, p.name, p.slug, p.manufacturer, p.model, p.specifications, p.price, p.currency, p.price_eur, p.offer_count, p.version
+        "#,
+    )
+    .bind(id)
+    .bind(snapshot.category_id)
+    .bind(&snapshot.name)
+    .bind(&snapshot.manufacturer)
+    .bind(&snapshot.model)
+    .bind(&snapshot.specifications)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to restore product {} to revision {}: {}", id, rev, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    // The snapshot's category no longer exists
+    .ok_or(StatusCode::CONFLICT)?;
+
+    follow_rename(&mut tx, &mut product)
+        .await
+        .map_err(slug_error)?;
+
+    let new_revision = record_revision(&mut *tx, &product, &actor, "restore")
+        .await
+        .map_err(revision_error)?;
+
+    AuditEntry::new(&actor, &request_id, "restore", "product", id)
+        .before(&before)
+        .after(&product)
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+    state.distributions.invalidate(before.category_id);
+    state.distributions.invalidate(product.category_id);
+
+    tracing::info!(
+        "Restored product {} to revision {} as revision {} by {}",
+        id,
+        rev,
+        new_revision,
+        actor
+    );
+
+    Ok(with_etag(StatusCode::OK, product))
+}
+
+async fn fetch_revision(
+    state: &AppState,
+    id: i32,
+    rev: i32,
+) -> Result<ProductRevision, StatusCode> {
+    sqlx::query_as::<_, ProductRevision>(
+        r#"
+        SELECT product_id, revision, action, actor_type, actor_id, actor_name, created_at, snapshot
+        FROM product_revisions
+        WHERE product_id = $1 AND revision = $2
+        "#,
+    )
+    .bind(id)
+    .bind(rev)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch revision {} of product {}: {}", rev, id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,197 @@
+use axum::extract::State;
+
+use crate::{
+    audit::{AuditEntry, RequestId},
+    auth::{
+        AdminAuth, CurrentUser, SESSION_TTL, hash_password, sign_session_token, verify_password,
+    },
+    models::{AssignRole, LoginRequest, LoginResponse, RegisterUser, User, UserResponse},
+    routes::{AppState, audit_error, begin, commit},
+};
+
+use axum::{Json, extract::Path, http::StatusCode};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+pub async fn register(
+    State(state): State<AppState>,
+    Json(payload): Json<RegisterUser>,
+) -> Result<(StatusCode, Json<UserResponse>), StatusCode> {
+    let email = payload.email.trim();
+    let display_name = payload.display_name.trim();
+
+    if !email.contains('@') || display_name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if payload.password.chars().count() < MIN_PASSWORD_LENGTH {
+        tracing::warn!("Rejected registration for '{}': password too short", email);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let existing: Option<i32> =
+        sqlx::query_scalar("SELECT id FROM users WHERE LOWER(email) = LOWER($1)")
+            .bind(email)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to check for existing user: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+    if existing.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let password_hash = hash_password(&payload.password).map_err(|e| {
+        tracing::error!("Failed to hash password: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (email, display_name, password_hash)
+        VALUES ($1, $2, $3)
+        RETURNING id, email, display_name, role
+        "#,
+    )
+    .bind(email)
+    .bind(display_name)
+    .bind(&password_hash)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create user: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tracing::info!("Registered user {} ({})", user.id, user.email);
+
+    Ok((StatusCode::CREATED, Json(UserResponse { user })))
+}
+
+pub async fn login(
+    State(state): State<AppState>,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, StatusCode> {
+    let row: Option<(i32, String, String, String, String)> = sqlx::query_as(
+        "SELECT id, email, display_name, role, password_hash FROM users WHERE LOWER(email) = LOWER($1)",
+    )
+    .bind(payload.email.trim())
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch user for login: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some((id, email, display_name, role, password_hash)) = row else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    if !verify_password(&payload.password, &password_hash) {
+        tracing::warn!("Failed login for user {}", id);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let expires_at = chrono::Utc::now() + SESSION_TTL;
+
+    let session_id: i32 = sqlx::query_scalar(
+        "INSERT INTO sessions (user_id, expires_at) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(id)
+    .bind(expires_at)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create session for user {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let token = sign_session_token(&state.session_secret, session_id, expires_at);
+
+    Ok(Json(LoginResponse {
+        token,
+        expires_at,
+        user: User {
+            id,
+            email,
+            display_name,
+            role: role.parse().map_err(|e| {
+                tracing::error!("User {} has an invalid role: {}", id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?,
+        },
+    }))
+}
+
+pub async fn logout(
+    current: CurrentUser,
+    State(state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE id = $1")
+        .bind(current.session_id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to revoke session {}: {}", current.session_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_current_user(current: CurrentUser) -> Json<UserResponse> {
+    Json(UserResponse { user: current.user })
+}
+
+pub async fn assign_role(
+    AdminAuth(actor): AdminAuth,
+    request_id: RequestId,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<AssignRole>,
+) -> Result<Json<UserResponse>, StatusCode> {
+    let mut tx = begin(&state).await?;
+
+    let before: String = sqlx::query_scalar("SELECT role FROM users WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch user {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET role = $2 WHERE id = $1 RETURNING id, email, display_name, role",
+    )
+    .bind(id)
+    .bind(payload.role.as_str())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to assign role to user {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Only the role is recorded, account details stay out of the audit log
+    AuditEntry::new(&actor, &request_id, "update", "user", id)
+        .before(&serde_json::json!({ "role": before }))
+        .after(&serde_json::json!({ "role": user.role }))
+        .record(&mut *tx)
+        .await
+        .map_err(audit_error)?;
+
+    commit(tx).await?;
+
+    tracing::info!(
+        "Assigned role {} to user {} by {}",
+        user.role,
+        user.id,
+        actor
+    );
+
+    Ok(Json(UserResponse { user }))
+}
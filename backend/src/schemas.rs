@@ -1,13 +1,29 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
+use crate::{
+    audit::AuditEntry,
+    computed::Expression,
+    dimensions::{LengthUnit, valid_order},
+    models::Category,
+    stats::numeric_type,
+};
+
+/// Specification field types understood by comparisons and validation.
+pub const FIELD_TYPES: &[&str] = &["number", "string", "boolean", "dimensions"];
 
 pub fn load_schemas() -> color_eyre::Result<CategorySchemas> {
     let schema_content = include_str!("schemas/schemas.yml");
 
-    let schemas: CategorySchemas = serde_yaml::from_str(&schema_content)
+    let schemas: CategorySchemas = serde_yaml::from_str(schema_content)
         .map_err(|e| color_eyre::eyre::eyre!("Failed to parse schemas.yml: {}", e))?;
 
+    for category in &schemas.categories {
+        category.validate().map_err(|e| {
+            color_eyre::eyre::eyre!("Invalid category '{}' in schemas.yml: {}", category.slug, e)
+        })?;
+    }
+
     Ok(schemas)
 }
 
@@ -16,26 +32,89 @@ pub async fn sync_categories(pool: &sqlx::PgPool) -> color_eyre::Result<()> {
 
     for category in schemas.categories {
         let schema_json = serde_json::to_value(&category.specifications)?;
+        let profiles_json = serde_json::to_value(&category.profiles)?;
+        let energy_json = category
+            .energy
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?;
+
+        let mut tx = pool.begin().await?;
+
+        let current: Option<(String, serde_json::Value, Option<serde_json::Value>)> =
+            sqlx::query_as(
+                "SELECT source, weight_profiles, energy_profile FROM categories WHERE slug = $1 FOR UPDATE",
+            )
+        .bind(&category.slug)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        // Categories edited through the API take precedence over schemas.yml
+        if current
+            .as_ref()
+            .is_some_and(|(source, _, _)| source == "api")
+        {
+            tracing::info!(
+                "Skipped category {} ({}): managed through the API",
+                category.name,
+                category.slug
+            );
+            continue;
+        }
 
-        sqlx::query(
+        let before = sqlx::query_as::<_, Category>(
+            "SELECT id, name, slug, description, specification_schema FROM categories WHERE slug = $1",
+        )
+        .bind(&category.slug)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let unchanged = before.as_ref().is_some_and(|b| {
+            b.name == category.name
+                && b.description.as_deref() == Some(category.description.as_str())
+                && b.specification_schema.as_ref() == Some(&schema_json)
+                && current.as_ref().is_some_and(|(_, profiles, energy)| {
+                    *profiles == profiles_json && *energy == energy_json
+                })
+        });
+
+        if unchanged {
+            tracing::info!("Category up to date: {} ({})", category.name, category.slug);
+            continue;
+        }
+
+        let after = sqlx::query_as::<_, Category>(
             r#"
-            INSERT INTO categories (name, slug, description, specification_schema)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO categories (name, slug, description, specification_schema, weight_profiles, energy_profile)
+            VALUES ($1, $2, $3, $4, $5, $6)
             ON CONFLICT (slug) 
             DO UPDATE SET 
                 name = EXCLUDED.name,
                 description = EXCLUDED.description,
                 specification_schema = EXCLUDED.specification_schema,
+                weight_profiles = EXCLUDED.weight_profiles,
+                energy_profile = EXCLUDED.energy_profile,
                 updated_at = NOW()
+            RETURNING id, name, slug, description, specification_schema
             "#,
         )
         .bind(&category.name)
         .bind(&category.slug)
         .bind(&category.description)
         .bind(&schema_json)
-        .execute(pool)
+        .bind(&profiles_json)
+        .bind(&energy_json)
+        .fetch_one(&mut *tx)
         .await?;
 
+        let mut entry = AuditEntry::system("sync", "category", &category.slug).after(&after);
+        if let Some(before) = &before {
+            entry = entry.before(before);
+        }
+        entry.record(&mut *tx).await?;
+
+        tx.commit().await?;
+
         tracing::info!("Synced category: {} ({})", category.name, category.slug);
     }
 
@@ -53,13 +132,244 @@ pub struct CategoryDefinition {
     pub name: String,
     pub description: String,
     pub specifications: HashMap<String, SpecificationField>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, WeightProfile>,
+    #[serde(default)]
+    pub energy: Option<EnergyProfile>,
+}
+
+/// Scoring weights by field. Fields must be `price` or numeric fields with a
+/// boolean `higher_is_better`; a weight of 0 leaves the field out.
+pub type WeightProfile = BTreeMap<String, f64>;
+
+/// Where a category's power draw comes from, for running-cost estimates.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EnergyProfile {
+    pub field: String, // watts with `hours_per_day`, kWh per cycle with `cycles_per_week`
+    pub usage: Usage,
+    pub default_usage: f64, // hours per day or cycles per week when the client gives none
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Usage {
+    HoursPerDay,
+    CyclesPerWeek,
+}
+
+impl CategoryDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_slug(&self.slug)?;
+
+        if self.name.trim().is_empty() {
+            return Err("name must not be empty".to_string());
+        }
+
+        validate_specifications(&self.specifications)?;
+
+        validate_profiles(&self.specifications, &self.profiles)?;
+
+        if let Some(energy) = &self.energy {
+            validate_energy(&self.specifications, energy)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct SpecificationField {
     #[serde(rename = "type")]
     pub field_type: String,
     pub label: String,
     pub unit: String,
     pub higher_is_better: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>, // computed from other fields, see `computed::Expression`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>, // dimensions only: order of unlabelled measurements, e.g. "hwd"
+}
+
+impl SpecificationField {
+    /// Whether the field holds numbers, see `stats::numeric_type`.
+    pub fn is_numeric(&self) -> bool {
+        numeric_type(&self.field_type, &self.unit)
+    }
+}
+
+pub fn validate_slug(slug: &str) -> Result<(), String> {
+    let valid = !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "slug '{}' must be non-empty lowercase ASCII letters, digits, '-' or '_'",
+            slug
+        ))
+    }
+}
+
+pub fn validate_specifications(
+    specifications: &HashMap<String, SpecificationField>,
+) -> Result<(), String> {
+    for (key, field) in specifications {
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(format!(
+                "field key '{}' must be lowercase ASCII letters, digits or '_'",
+                key
+            ));
+        }
+
+        if !FIELD_TYPES.contains(&field.field_type.as_str()) {
+            return Err(format!(
+                "field '{}' has unknown type '{}' (expected one of {})",
+                key,
+                field.field_type,
+                FIELD_TYPES.join(", ")
+            ));
+        }
+
+        if field.label.trim().is_empty() {
+            return Err(format!("field '{}' must have a label", key));
+        }
+
+        if let Some(expression) = &field.expression {
+            validate_expression(key, field, expression, specifications)?;
+        }
+
+        if field.field_type == "dimensions" && LengthUnit::parse(&field.unit).is_none() {
+            return Err(format!(
+                "dimensions field '{}' needs a unit of mm, cm or inches",
+                key
+            ));
+        }
+
+        if let Some(order) = &field.order {
+            if field.field_type != "dimensions" {
+                return Err(format!(
+                    "only dimensions fields can set an order ('{}')",
+                    key
+                ));
+            }
+            if !valid_order(order) {
+                return Err(format!(
+                    "order of '{}' must use each of w, h and d once",
+                    key
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Computed fields are numbers over `price` and stored number or string fields.
+/// They cannot read other computed fields.
+fn validate_expression(
+    key: &str,
+    field: &SpecificationField,
+    expression: &str,
+    specifications: &HashMap<String, SpecificationField>,
+) -> Result<(), String> {
+    if !field.is_numeric() {
+        return Err(format!("computed field '{}' must be a number field", key));
+    }
+
+    let parsed = Expression::parse(expression)
+        .map_err(|e| format!("expression of '{}' is invalid: {}", key, e))?;
+
+    for input in parsed.fields() {
+        let readable = input == "price"
+            || specifications.get(input).is_some_and(|f| {
+                f.expression.is_none() && (f.field_type == "number" || f.field_type == "string")
+            });
+        if !readable {
+            return Err(format!(
+                "expression of '{}' reads '{}', which is not price or a stored number or string field",
+                key, input
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_profiles(
+    specifications: &HashMap<String, SpecificationField>,
+    profiles: &BTreeMap<String, WeightProfile>,
+) -> Result<(), String> {
+    for (name, profile) in profiles {
+        validate_slug(name).map_err(|e| format!("profile {}", e))?;
+        validate_weights(specifications, profile)
+            .map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+
+    Ok(())
+}
+
+pub fn validate_weights(
+    specifications: &HashMap<String, SpecificationField>,
+    weights: &WeightProfile,
+) -> Result<(), String> {
+    for (field, weight) in weights {
+        let scorable = field == "price"
+            || specifications
+                .get(field)
+                .is_some_and(|f| f.is_numeric() && f.higher_is_better.is_some());
+        if !scorable {
+            return Err(format!(
+                "field '{}' is not price or a numeric field with higher_is_better set",
+                field
+            ));
+        }
+
+        if !weight.is_finite() || *weight < 0.0 {
+            return Err(format!(
+                "weight of '{}' must be a non-negative number",
+                field
+            ));
+        }
+    }
+
+    if !weights.values().any(|w| *w > 0.0) {
+        return Err("at least one weight must be positive".to_string());
+    }
+
+    Ok(())
+}
+
+pub fn validate_energy(
+    specifications: &HashMap<String, SpecificationField>,
+    energy: &EnergyProfile,
+) -> Result<(), String> {
+    let readable = specifications
+        .get(&energy.field)
+        .is_some_and(|f| f.field_type == "number" || f.field_type == "string");
+    if !readable {
+        return Err(format!(
+            "energy field '{}' is not a number or string field",
+            energy.field
+        ));
+    }
+
+    let max_usage = match energy.usage {
+        Usage::HoursPerDay => 24.0,
+        Usage::CyclesPerWeek => f64::MAX,
+    };
+    if !energy.default_usage.is_finite()
+        || energy.default_usage <= 0.0
+        || energy.default_usage > max_usage
+    {
+        return Err("energy default_usage is out of range".to_string());
+    }
+
+    Ok(())
 }
@@ -0,0 +1,182 @@
+use crate::{
+    compare::{numeric_value, round},
+    computed::computed_fields,
+    models::{Category, Product, ScoreContribution},
+    schemas::WeightProfile,
+    stats::{CategoryDistribution, field_value, has_numeric_type},
+};
+
+/// Overall score of a product from 0 to 100: the mean of its category
+/// percentiles over the numeric fields whose `higher_is_better` is set,
+/// leaving out price and fields computed from it, with missing values
+/// counting as 0. `None` when the product has none of them.
+pub fn score(
+    category: &Category,
+    distribution: &CategoryDistribution,
+    product: &Product,
+) -> Option<f64> {
+    weighted_score(category, distribution, product, &default_weights(category)).map(|s| s.score)
+}
+
+/// A product's weighted score and how each field contributed to it.
+#[derive(Debug)]
+pub struct WeightedScore {
+    pub score: f64,
+    pub contributions: Vec<ScoreContribution>, // largest contribution first
+    pub missing: Vec<String>,                  // weighted fields the product has no value for
+    pub coverage: f64, // share of the weights the product has values for, 0-1
+}
+
+/// Fields that can carry a weight, with their `higher_is_better`: price
+/// (lower is better) and the numeric fields whose `higher_is_better` is set.
+pub fn scorable_fields(category: &Category) -> Vec<(String, bool)> {
+    let mut fields = vec![("price".to_string(), false)];
+
+    if let Some(schema) = category
+        .specification_schema
+        .as_ref()
+        .and_then(|s| s.as_object())
+    {
+        for (field, def) in schema {
+            if !has_numeric_type(def) {
+                continue;
+            }
+            if let Some(higher_is_better) = def.get("higher_is_better").and_then(|v| v.as_bool()) {
+                fields.push((field.clone(), higher_is_better));
+            }
+        }
+    }
+
+    fields
+}
+
+/// Equal weights on every scorable field, as used by `score`. Price and the
+/// fields computed from it, such as price per core, are left out so a score
+/// plotted against price doesn't count it twice.
+pub fn default_weights(category: &Category) -> WeightProfile {
+    let price_derived: Vec<String> = computed_fields(category)
+        .into_iter()
+        .filter(|(_, expression)| expression.fields().contains(&"price"))
+        .map(|(field, _)| field)
+        .collect();
+
+    scorable_fields(category)
+        .into_iter()
+        .filter(|(field, _)| field != "price" && !price_derived.contains(field))
+        .map(|(field, _)| (field, 1.0))
+        .collect()
+}
+
+/// Parses explicit weights given as `field:weight,field:weight`.
+pub fn parse_weights(category: &Category, input: &str) -> Result<WeightProfile, String> {
+    let scorable = scorable_fields(category);
+    let mut weights = WeightProfile::new();
+
+    for part in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (field, weight) = part
+            .split_once(':')
+            .ok_or_else(|| format!("expected field:weight, got '{}'", part))?;
+        let field = field.trim();
+
+        if !scorable.iter().any(|(f, _)| f == field) {
+            return Err(format!(
+                "field '{}' is not price or a numeric field with higher_is_better set",
+                field
+            ));
+        }
+
+        let weight: f64 = weight
+            .trim()
+            .parse()
+            .map_err(|_| format!("weight of '{}' must be a number", field))?;
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(format!(
+                "weight of '{}' must be a non-negative number",
+                field
+            ));
+        }
+
+        weights.insert(field.to_string(), weight);
+    }
+
+    if !weights.values().any(|w| *w > 0.0) {
+        return Err("at least one weight must be positive".to_string());
+    }
+
+    Ok(weights)
+}
+
+/// Weighted mean of the product's category percentiles, from 0 to 100.
+/// Weighted fields the product has no value for count as a percentile of 0,
+/// so a product can't rank high on the few fields it has; the contributions
+/// of the other fields add up to the score. Weights on fields that are no
+/// longer scorable are ignored. `None` when no weighted field has a value.
+pub fn weighted_score(
+    category: &Category,
+    distribution: &CategoryDistribution,
+    product: &Product,
+    weights: &WeightProfile,
+) -> Option<WeightedScore> {
+    let labels = category
+        .specification_schema
+        .as_ref()
+        .and_then(|s| s.as_object());
+
+    let mut present = Vec::new();
+    let mut missing = Vec::new();
+    for (field, higher_is_better) in scorable_fields(category) {
+        let Some(&weight) = weights.get(&field).filter(|w| **w > 0.0) else {
+            continue;
+        };
+        let percentile = numeric_value(&field_value(product, &field))
+            .and_then(|value| distribution.percentile_rank(&field, value, Some(higher_is_better)));
+        match percentile {
+            Some(percentile) => present.push((field, weight, percentile)),
+            None => missing.push((field, weight)),
+        }
+    }
+
+    let covered: f64 = present.iter().map(|(_, weight, _)| weight).sum();
+    let total = covered + missing.iter().map(|(_, weight)| weight).sum::<f64>();
+    if present.is_empty() || covered <= 0.0 {
+        return None;
+    }
+
+    let mut contributions: Vec<ScoreContribution> = present
+        .into_iter()
+        .map(|(field, weight, percentile)| {
+            let label = labels
+                .and_then(|s| s.get(&field))
+                .and_then(|def| def.get("label"))
+                .and_then(|l| l.as_str())
+                .unwrap_or(if field == "price" { "Price" } else { &field })
+                .to_string();
+            let share = weight / total;
+            ScoreContribution {
+                field,
+                label,
+                weight: share,
+                percentile,
+                contribution: share * percentile,
+            }
+        })
+        .collect();
+
+    let score = contributions.iter().map(|c| c.contribution).sum();
+
+    contributions.sort_by(|a, b| b.contribution.total_cmp(&a.contribution));
+    for c in &mut contributions {
+        c.weight = round(c.weight);
+        c.percentile = round(c.percentile);
+        c.contribution = round(c.contribution);
+    }
+    let mut missing: Vec<String> = missing.into_iter().map(|(field, _)| field).collect();
+    missing.sort();
+
+    Some(WeightedScore {
+        score,
+        contributions,
+        missing,
+        coverage: covered / total,
+    })
+}
@@ -0,0 +1,171 @@
+use crate::{
+    compare::numeric_value,
+    models::{Category, FieldDistance, NearbyProduct, Product},
+    stats::{boolean_value, field_value, is_numeric_field, sorted_numbers},
+};
+
+/// Number of most different fields reported per similar product.
+const MOST_DIFFERENT_FIELDS: usize = 3;
+
+enum FeatureKind {
+    /// Scaled by the category's range, so every field weighs the same
+    Numeric {
+        min: f64,
+        range: f64,
+    },
+    Boolean,
+    /// Text strings count as the same or different values, like an enum
+    Categorical,
+}
+
+struct Feature {
+    field: String,
+    label: String,
+    kind: FeatureKind,
+}
+
+/// Ranks `candidates` by their distance to `product`: the root mean square of
+/// per-field distances over the category's numeric, boolean and string fields,
+/// each normalized to 0-1. A value missing on one side counts as fully
+/// different, fields missing on both sides are skipped.
+pub fn nearest_products(
+    category: &Category,
+    product: &Product,
+    candidates: &[Product],
+    include_price: bool,
+    limit: usize,
+) -> Vec<NearbyProduct> {
+    let features = features(category, candidates, include_price);
+
+    let mut nearby: Vec<NearbyProduct> = candidates
+        .iter()
+        .filter(|c| c.id != product.id)
+        .filter_map(|candidate| {
+            let mut distances: Vec<FieldDistance> = features
+                .iter()
+                .filter_map(|feature| {
+                    let value = field_value(product, &feature.field);
+                    let other_value = field_value(candidate, &feature.field);
+                    let distance = feature_distance(&feature.kind, &value, &other_value)?;
+
+                    Some(FieldDistance {
+                        field: feature.field.clone(),
+                        label: feature.label.clone(),
+                        distance,
+                        value,
+                        other_value,
+                    })
+                })
+                .collect();
+
+            if distances.is_empty() {
+                return None;
+            }
+
+            let distance = (distances.iter().map(|d| d.distance.powi(2)).sum::<f64>()
+                / distances.len() as f64)
+                .sqrt();
+
+            distances.retain(|d| d.distance > 0.0);
+            distances.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+            distances.truncate(MOST_DIFFERENT_FIELDS);
+
+            Some(NearbyProduct {
+                product: candidate.clone(),
+                distance,
+                most_different: distances,
+            })
+        })
+        .collect();
+
+    nearby.sort_by(|a, b| {
+        a.distance
+            .total_cmp(&b.distance)
+            .then(a.product.id.cmp(&b.product.id))
+    });
+    nearby.truncate(limit);
+    nearby
+}
+
+fn features(category: &Category, products: &[Product], include_price: bool) -> Vec<Feature> {
+    let mut fields: Vec<(String, String, bool, &str)> = Vec::new();
+    if include_price {
+        fields.push(("price".to_string(), "Price".to_string(), true, "number"));
+    }
+
+    if let Some(schema) = category
+        .specification_schema
+        .as_ref()
+        .and_then(|s| s.as_object())
+    {
+        for (field_name, field_def) in schema {
+            let label = field_def
+                .get("label")
+                .and_then(|v| v.as_str())
+                .unwrap_or(field_name);
+            let field_type = field_def
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("string");
+            let numeric = is_numeric_field(field_def, field_name, products);
+            fields.push((field_name.clone(), label.to_string(), numeric, field_type));
+        }
+    }
+
+    fields
+        .into_iter()
+        .filter_map(|(field, label, numeric, field_type)| {
+            let kind = match field_type {
+                _ if numeric => {
+                    let numbers = sorted_numbers(products, &field);
+                    let (min, max) = (*numbers.first()?, *numbers.last()?);
+                    FeatureKind::Numeric {
+                        min,
+                        range: max - min,
+                    }
+                }
+                "boolean" => FeatureKind::Boolean,
+                _ => FeatureKind::Categorical,
+            };
+
+            Some(Feature { field, label, kind })
+        })
+        .collect()
+}
+
+fn feature_distance(
+    kind: &FeatureKind,
+    value: &serde_json::Value,
+    other: &serde_json::Value,
+) -> Option<f64> {
+    let (a, b) = match kind {
+        FeatureKind::Numeric { min, range } => (
+            numeric_value(value).map(|v| if *range > 0.0 { (v - min) / range } else { 0.0 }),
+            numeric_value(other).map(|v| if *range > 0.0 { (v - min) / range } else { 0.0 }),
+        ),
+        FeatureKind::Boolean => (
+            boolean_value(value).map(f64::from),
+            boolean_value(other).map(f64::from),
+        ),
+        FeatureKind::Categorical => {
+            let text = |v: &serde_json::Value| match v {
+                serde_json::Value::Null => None,
+                serde_json::Value::String(s) if s.trim().is_empty() => None,
+                serde_json::Value::String(s) => Some(s.trim().to_lowercase()),
+                other => Some(other.to_string()),
+            };
+
+            return match (text(value), text(other)) {
+                (None, None) => None,
+                (Some(a), Some(b)) => Some(if a == b { 0.0 } else { 1.0 }),
+                _ => Some(1.0),
+            };
+        }
+    };
+
+    match (a, b) {
+        (None, None) => None,
+        (Some(a), Some(b)) => Some((a - b).abs().min(1.0)),
+        _ => Some(1.0),
+    }
+}
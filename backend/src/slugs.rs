@@ -0,0 +1,131 @@
+use crate::models::Product;
+
+/// URL slug for a product name: lowercase ASCII letters and digits separated
+/// by single dashes, as in "intel-core-i9-13900k".
+pub fn slugify(name: &str) -> String {
+    let slug = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        "product".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Picks the slug for a product called `name`: the plain slug of the name, or
+/// the first free numbered variant of it. Slugs the product already claimed
+/// for the same name are reused, so renaming a product back restores its old
+/// slug. Whether a slug is a numbered variant comes from the name it was
+/// claimed for, as "rtx-4090" may also be the plain slug of "RTX 4090".
+pub async fn available_slug(
+    conn: &mut sqlx::PgConnection,
+    name: &str,
+    product_id: Option<i32>,
+) -> Result<String, sqlx::Error> {
+    let base = slugify(name);
+
+    let taken = sqlx::query_as::<_, (String, i32, String)>(
+        r#"
+        SELECT slug, product_id, base
+        FROM product_slugs
+        WHERE slug = $1 OR slug ~ ('^' || $1 || '-[0-9]+$')
+        "#,
+    )
+    .bind(&base)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if let Some(id) = product_id
+        && let Some((slug, _, _)) = taken
+            .iter()
+            .filter(|(_, owner, claimed_for)| *owner == id && *claimed_for == base)
+            .min_by_key(|(slug, _, _)| slug.len())
+    {
+        return Ok(slug.clone());
+    }
+
+    let is_taken = |slug: &str| taken.iter().any(|(taken, _, _)| taken == slug);
+    if !is_taken(&base) {
+        return Ok(base);
+    }
+
+    Ok((2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|slug| !is_taken(slug))
+        .expect("a free numbered slug"))
+}
+
+/// Records `slug`, picked for a name whose plain slug is `base`, as belonging
+/// to the product and makes it the current one. Returns false when another
+/// product holds the slug, e.g. one created concurrently with the same name.
+pub async fn claim_slug(
+    conn: &mut sqlx::PgConnection,
+    product_id: i32,
+    slug: &str,
+    base: &str,
+) -> Result<bool, sqlx::Error> {
+    // Waits for a concurrent claim of the same slug to commit or roll back
+    sqlx::query(
+        r#"
+        INSERT INTO product_slugs (slug, product_id, base)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (slug) DO NOTHING
+        "#,
+    )
+    .bind(slug)
+    .bind(product_id)
+    .bind(base)
+    .execute(&mut *conn)
+    .await?;
+
+    let owner: i32 = sqlx::query_scalar("SELECT product_id FROM product_slugs WHERE slug = $1")
+        .bind(slug)
+        .fetch_one(&mut *conn)
+        .await?;
+    if owner != product_id {
+        return Ok(false);
+    }
+
+    sqlx::query("UPDATE products SET slug = $1 WHERE id = $2")
+        .bind(slug)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(true)
+}
+
+/// Gives the product the slug `available_slug` picks for `name`, moving on to
+/// the next free one when a concurrent write claims it first.
+pub async fn assign_slug(
+    conn: &mut sqlx::PgConnection,
+    product_id: i32,
+    name: &str,
+) -> Result<String, sqlx::Error> {
+    let base = slugify(name);
+    loop {
+        let slug = available_slug(conn, name, Some(product_id)).await?;
+        if claim_slug(conn, product_id, &slug, &base).await? {
+            return Ok(slug);
+        }
+    }
+}
+
+/// Moves the product to a slug matching its current name, keeping the old one
+/// as an alias. Call it after any change that may have renamed the product.
+pub async fn follow_rename(
+    conn: &mut sqlx::PgConnection,
+    product: &mut Product,
+) -> Result<(), sqlx::Error> {
+    let slug = available_slug(conn, &product.name, Some(product.id)).await?;
+    if slug != product.slug {
+        product.slug = assign_slug(conn, product.id, &product.name).await?;
+    }
+
+    Ok(())
+}
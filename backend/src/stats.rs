@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use serde_json::json;
+
+use crate::{
+    compare::numeric_value,
+    models::{
+        BooleanStats, Category, FieldStats, HistogramBin, NumericStats, Percentiles, Product,
+        ValueCount,
+    },
+};
+
+const HISTOGRAM_BINS: usize = 10;
+const TOP_VALUES: usize = 10;
+
+/// Per-field distributions for a category: price, then every field of its
+/// specification schema, summarized according to the field's type.
+pub fn category_stats(category: &Category, products: &[Product]) -> Vec<FieldStats> {
+    let mut fields = vec![field_stats(
+        "price", "Price", "EUR", "number", true, products,
+    )];
+
+    if let Some(schema) = category
+        .specification_schema
+        .as_ref()
+        .and_then(|s| s.as_object())
+    {
+        for (field_name, field_def) in schema {
+            let definition = |key: &str| field_def.get(key).and_then(|v| v.as_str());
+            fields.push(field_stats(
+                field_name,
+                definition("label").unwrap_or(field_name),
+                definition("unit").unwrap_or(""),
+                definition("type").unwrap_or("string"),
+                is_numeric_field(field_def, field_name, products),
+                products,
+            ));
+        }
+    }
+
+    fields
+}
+
+/// A product's value for a field. `price` is the product's price in euros, so
+/// products priced in different currencies compare; anything else is looked
+/// up in its specifications.
+pub fn field_value(product: &Product, field: &str) -> serde_json::Value {
+    if field == "price" {
+        return json!(product.price_eur.as_ref().map(|p| p.to_string()));
+    }
+
+    product
+        .specifications
+        .as_ref()
+        .and_then(|specs| specs.get(field))
+        .cloned()
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// Whether a field's definition makes it a number: `number` fields, and
+/// `string` fields that have a unit, such as a noise level stored as "58"
+/// with unit "dB". Scoring, Pareto axes and schema validation go by this,
+/// as they can't look at the values.
+pub fn numeric_type(field_type: &str, unit: &str) -> bool {
+    field_type == "number" || (field_type == "string" && !unit.trim().is_empty())
+}
+
+/// `numeric_type` of a field definition from a category's schema.
+pub fn has_numeric_type(field_def: &serde_json::Value) -> bool {
+    let definition = |key: &str| field_def.get(key).and_then(|v| v.as_str());
+    numeric_type(
+        definition("type").unwrap_or("string"),
+        definition("unit").unwrap_or(""),
+    )
+}
+
+/// Whether a field is compared as a number: fields of a numeric type, and
+/// `string` fields whose values all parse as numbers.
+pub fn is_numeric_field(field_def: &serde_json::Value, field: &str, products: &[Product]) -> bool {
+    if has_numeric_type(field_def) {
+        return true;
+    }
+    if field_def
+        .get("type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("string")
+        != "string"
+    {
+        return false;
+    }
+
+    let mut values = products
+        .iter()
+        .map(|p| field_value(p, field))
+        .filter(|v| !v.is_null() && v.as_str().is_none_or(|s| !s.trim().is_empty()))
+        .peekable();
+    values.peek().is_some() && values.all(|v| numeric_value(&v).is_some())
+}
+
+/// Numeric values of a field across products, sorted ascending.
+pub fn sorted_numbers(products: &[Product], field: &str) -> Vec<f64> {
+    let mut numbers: Vec<f64> = products
+        .iter()
+        .filter_map(|p| numeric_value(&field_value(p, field)))
+        .collect();
+    numbers.sort_by(f64::total_cmp);
+    numbers
+}
+
+/// Linearly interpolated percentile (0-100) of ascending, non-empty values.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+fn field_stats(
+    field: &str,
+    label: &str,
+    unit: &str,
+    field_type: &str,
+    numeric: bool,
+    products: &[Product],
+) -> FieldStats {
+    let mut stats = FieldStats {
+        field: field.to_string(),
+        label: label.to_string(),
+        unit: unit.to_string(),
+        field_type: field_type.to_string(),
+        count: 0,
+        missing: 0,
+        numeric: None,
+        boolean: None,
+        top_values: None,
+    };
+
+    match field_type {
+        _ if numeric => {
+            let numbers = sorted_numbers(products, field);
+            stats.count = numbers.len();
+            stats.numeric = numeric_stats(&numbers);
+        }
+        "boolean" => {
+            let flags: Vec<bool> = products
+                .iter()
+                .filter_map(|p| boolean_value(&field_value(p, field)))
+                .collect();
+            let true_count = flags.iter().filter(|f| **f).count();
+            stats.count = flags.len();
+            stats.boolean = (!flags.is_empty()).then(|| BooleanStats {
+                true_count,
+                false_count: flags.len() - true_count,
+                true_share: true_count as f64 / flags.len() as f64,
+            });
+        }
+        _ => {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for product in products {
+                let value = match field_value(product, field) {
+                    serde_json::Value::Null => continue,
+                    serde_json::Value::String(s) if s.trim().is_empty() => continue,
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                *counts.entry(value).or_default() += 1;
+                stats.count += 1;
+            }
+
+            let mut top_values: Vec<ValueCount> = counts
+                .into_iter()
+                .map(|(value, count)| ValueCount { value, count })
+                .collect();
+            top_values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            top_values.truncate(TOP_VALUES);
+            stats.top_values = Some(top_values);
+        }
+    }
+
+    stats.missing = products.len() - stats.count;
+    stats
+}
+
+fn numeric_stats(sorted: &[f64]) -> Option<NumericStats> {
+    let (&min, &max) = (sorted.first()?, sorted.last()?);
+    let mean = sorted.iter().sum::<f64>() / sorted.len() as f64;
+
+    Some(NumericStats {
+        min,
+        max,
+        mean,
+        median: percentile(sorted, 50.0),
+        percentiles: Percentiles {
+            p10: percentile(sorted, 10.0),
+            p25: percentile(sorted, 25.0),
+            p50: percentile(sorted, 50.0),
+            p75: percentile(sorted, 75.0),
+            p90: percentile(sorted, 90.0),
+        },
+        histogram: histogram(sorted, min, max),
+    })
+}
+
+/// Equal-width bins between min and max, or a single bin when all values match.
+fn histogram(sorted: &[f64], min: f64, max: f64) -> Vec<HistogramBin> {
+    if min == max {
+        return vec![HistogramBin {
+            from: min,
+            to: max,
+            count: sorted.len(),
+        }];
+    }
+
+    let width = (max - min) / HISTOGRAM_BINS as f64;
+    let mut bins: Vec<HistogramBin> = (0..HISTOGRAM_BINS)
+        .map(|i| HistogramBin {
+            from: min + width * i as f64,
+            to: min + width * (i + 1) as f64,
+            count: 0,
+        })
+        .collect();
+
+    for value in sorted {
+        let index = (((value - min) / width) as usize).min(HISTOGRAM_BINS - 1);
+        bins[index].count += 1;
+    }
+
+    bins
+}
+
+pub fn boolean_value(value: &serde_json::Value) -> Option<bool> {
+    match value {
+        serde_json::Value::Bool(b) => Some(*b),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Sorted values of every numeric field in a category, price included, for
+/// ranking a single product's values against the whole category.
+#[derive(Debug)]
+pub struct CategoryDistribution {
+    fields: HashMap<String, Vec<f64>>,
+}
+
+impl CategoryDistribution {
+    pub fn new(category: &Category, products: &[Product]) -> Self {
+        let mut fields = HashMap::new();
+        fields.insert("price".to_string(), sorted_numbers(products, "price"));
+
+        if let Some(schema) = category
+            .specification_schema
+            .as_ref()
+            .and_then(|s| s.as_object())
+        {
+            for (field_name, field_def) in schema {
+                if is_numeric_field(field_def, field_name, products) {
+                    fields.insert(field_name.clone(), sorted_numbers(products, field_name));
+                }
+            }
+        }
+
+        CategoryDistribution { fields }
+    }
+
+    /// Percentile (0-100) of `value` among the category's values of `field`,
+    /// counting ties as half below. With `higher_is_better: false` the scale is
+    /// flipped, so a higher percentile is always the better value.
+    pub fn percentile_rank(
+        &self,
+        field: &str,
+        value: f64,
+        higher_is_better: Option<bool>,
+    ) -> Option<f64> {
+        let sorted = self.fields.get(field).filter(|v| !v.is_empty())?;
+        let below = sorted.partition_point(|v| *v < value);
+        let equal = sorted[below..].partition_point(|v| *v <= value);
+        let rank = (below as f64 + equal as f64 / 2.0) / sorted.len() as f64 * 100.0;
+
+        Some(if higher_is_better == Some(false) {
+            100.0 - rank
+        } else {
+            rank
+        })
+    }
+}
+
+/// Category distributions by category id. Entries are built on first use and
+/// must be invalidated whenever the category's approved products change.
+///
+/// Every invalidation bumps the category's generation, so a distribution built
+/// from products read before it is not cached afterwards.
+#[derive(Debug, Default)]
+pub struct DistributionCache {
+    categories: RwLock<HashMap<i32, CacheSlot>>,
+}
+
+#[derive(Debug, Default)]
+struct CacheSlot {
+    generation: u64,
+    distribution: Option<Arc<CategoryDistribution>>,
+}
+
+impl DistributionCache {
+    pub fn get(&self, category_id: i32) -> Option<Arc<CategoryDistribution>> {
+        self.categories
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&category_id)
+            .and_then(|slot| slot.distribution.clone())
+    }
+
+    /// The category's generation, to read before fetching its products.
+    pub fn generation(&self, category_id: i32) -> u64 {
+        self.categories
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&category_id)
+            .map_or(0, |slot| slot.generation)
+    }
+
+    /// Caches the distribution unless the category was invalidated since
+    /// `generation` was read.
+    pub fn insert(
+        &self,
+        category_id: i32,
+        generation: u64,
+        distribution: Arc<CategoryDistribution>,
+    ) {
+        let mut categories = self.categories.write().unwrap_or_else(|e| e.into_inner());
+        let slot = categories.entry(category_id).or_default();
+        if slot.generation == generation {
+            slot.distribution = Some(distribution);
+        }
+    }
+
+    pub fn invalidate(&self, category_id: i32) {
+        let mut categories = self.categories.write().unwrap_or_else(|e| e.into_inner());
+        let slot = categories.entry(category_id).or_default();
+        slot.generation += 1;
+        slot.distribution = None;
+    }
+}
@@ -1,36 +1,49 @@
 // compare.js
 const API_URL = 'https://utilbench-api.tomkoid.cz/api/v1/products';
+const CATEGORIES_API_URL = 'https://utilbench-api.tomkoid.cz/api/v1/categories';
+
+// formatSpec comes from format.js
 
 document.addEventListener('DOMContentLoaded', async () => {
     const params = new URLSearchParams(window.location.search);
-    const name1 = params.get('product1');
-    const name2 = params.get('product2');
+    const slug1 = params.get('product1');
+    const slug2 = params.get('product2');
     
-    if (!name1 || !name2) return;
+    if (!slug1 || !slug2) return;
+
+    async function fetchJson(url) {
+        const res = await fetch(url);
+        if (!res.ok) throw new Error(`${res.status} ${url}`);
+        return res.json();
+    }
 
-    let products = [];
+    let prod1, prod2, options1, options2;
     try {
-        const res = await fetch(API_URL);
-        const data = await res.json();
-        products = data.products || data;
+        [prod1, prod2] = await Promise.all([
+            fetchJson(`${API_URL}/by-slug/${encodeURIComponent(slug1)}`).then(d => d.product),
+            fetchJson(`${API_URL}/by-slug/${encodeURIComponent(slug2)}`).then(d => d.product),
+        ]);
     } catch (e) {
-        document.getElementById('compare-content').innerHTML = '<p>Chyba při načítání produktů.</p>';
+        document.getElementById('compare-content').innerHTML = '<p>Produkty nenalezeny.</p>';
         return;
     }
 
-    const prod1 = products.find(p => p.name === decodeURIComponent(name1));
-    const prod2 = products.find(p => p.name === decodeURIComponent(name2));
-    
-    if (!prod1 || !prod2) {
-        document.getElementById('compare-content').innerHTML = '<p>Produkty nenalezeny.</p>';
+    // Only products of the same category are offered in the selects
+    try {
+        const { categories } = await fetchJson(CATEGORIES_API_URL);
+        const categoryProducts = id => {
+            const category = categories.find(c => c.id === id);
+            return fetchJson(`${CATEGORIES_API_URL}/${category.slug}/products`).then(d => d.products);
+        };
+        [options1, options2] = await Promise.all([
+            categoryProducts(prod1.category_id),
+            categoryProducts(prod2.category_id),
+        ]);
+    } catch (e) {
+        document.getElementById('compare-content').innerHTML = '<p>Chyba při načítání produktů.</p>';
         return;
     }
 
-    const cat1 = prod1.category_id || prod1.categoryId || prod1.category;
-    const cat2 = prod2.category_id || prod2.categoryId || prod2.category;
-    const options1 = products.filter(p => (p.category_id || p.categoryId || p.category) == cat1);
-    const options2 = products.filter(p => (p.category_id || p.categoryId || p.category) == cat2);
-
     function renderSelect(selectedName, options, selectId) {
         const selectedProduct = options.find(p => p.name === selectedName);
         let imageUrl = '';
@@ -47,7 +60,7 @@ document.addEventListener('DOMContentLoaded', async () => {
             <span style="display:inline-flex;align-items:center;gap:10px;background:#fff;border-radius:8px;padding:0 0 0 8px;box-shadow:0 2px 8px rgba(0,0,0,0.04);border:1px solid #ccc;min-width:220px;max-width:350px;">
                 <img src="${imageUrl}" alt="mini" style="width:32px;height:32px;object-fit:contain;border-radius:6px;background:#f4f6f8;box-shadow:0 1px 3px rgba(0,0,0,0.04);">
                 <select id="${selectId}" style="font-size:1.1em;padding:10px 18px 10px 6px;border:none;outline:none;background:transparent;min-width:160px;max-width:300px;">
-                    ${options.map(p => `<option value="${encodeURIComponent(p.name)}"${p.name === selectedName ? ' selected' : ''}>${p.name}</option>`).join('')}
+                    ${options.map(p => `<option value="${p.slug}"${p.name === selectedName ? ' selected' : ''}>${p.name}</option>`).join('')}
                 </select>
             </span>
         `;
@@ -89,8 +102,8 @@ document.addEventListener('DOMContentLoaded', async () => {
             </thead>
             <tbody>
                 ${allKeys.map(key => {
-                    let v1 = specs1[key] !== undefined ? specs1[key] : '<span style="color:#bbb">—</span>';
-                    let v2 = specs2[key] !== undefined ? specs2[key] : '<span style="color:#bbb">—</span>';
+                    let v1 = specs1[key] !== undefined ? formatSpec(specs1[key]) : '<span style="color:#bbb">—</span>';
+                    let v2 = specs2[key] !== undefined ? formatSpec(specs2[key]) : '<span style="color:#bbb">—</span>';
                     [v1, v2] = highlight(key, v1, v2);
                     return `
                         <tr>
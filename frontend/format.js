@@ -0,0 +1,10 @@
+// format.js - display helpers shared by index.html, compare.html and script.js
+
+// Format a specification value for display; dimensions are stored as objects
+function formatSpec(value) {
+    if (value === null || value === undefined) return '—';
+    if (typeof value === 'object' && 'width' in value && 'height' in value && 'depth' in value) {
+        return `${value.width} × ${value.height} × ${value.depth} ${value.unit || ''}`.trim();
+    }
+    return value;
+}
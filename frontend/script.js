@@ -4,6 +4,8 @@ const CATEGORIES_API_URL = 'https://utilbench-api.tomkoid.cz/api/v1/categories';
 let products = [];
 let categories = [];
 
+// formatSpec comes from format.js
+
 // Check if using SPA version
 function isSPA() {
     return window.location.pathname.includes('index.html');
@@ -115,7 +117,7 @@ function displayBenchmark(data) {
         if (product.specifications && typeof product.specifications === 'object') {
             specsHTML = '<div class="card-specs"><table>';
             for (const [key, value] of Object.entries(product.specifications)) {
-                specsHTML += `<tr><th>${key}</th><td>${value}</td></tr>`;
+                specsHTML += `<tr><th>${key}</th><td>${formatSpec(value)}</td></tr>`;
             }
             specsHTML += '</table></div>';
         }